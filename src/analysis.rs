@@ -0,0 +1,210 @@
+use crate::common::*;
+use crate::computer_moves::*;
+use crate::game_state::*;
+use crate::game_status::*;
+use crate::game_textures::*;
+use crate::notation::*;
+use crate::piece::*;
+use crate::program_options::*;
+use crate::search::*;
+use bevy::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+const SHOWN_LINE_MOVES: usize = 5;
+const EVAL_BAR_SIZE: (f32, f32) = (24., 300.);
+const EVAL_BAR_COLORS: (Color, Color) =
+    (Color::rgb(0.95, 0.95, 0.95), Color::rgb(0.15, 0.15, 0.15));
+
+pub struct AnalysisResult {
+    pub depth: i32,
+    pub lines: Vec<PvLine>,
+}
+
+// The position searched in the background. Every position gets its own stop
+// flag and result, so a search that is still winding down can not overwrite
+// the result of the next position.
+#[derive(Default)]
+pub struct Analysis {
+    game_state: Option<GameState>,
+    stop: Arc<AtomicBool>,
+    result: Arc<Mutex<Option<AnalysisResult>>>,
+}
+
+impl Analysis {
+    fn start(&mut self, game_state: &GameState) -> GameStatus {
+        self.stop.store(true, Ordering::Relaxed);
+        self.game_state = Some(game_state.clone());
+        self.stop = Arc::new(AtomicBool::new(false));
+        self.result = Arc::new(Mutex::new(None));
+        let status = GameStatus::of(game_state, None);
+        if status.is_game_over() {
+            return status;
+        }
+        let game_state = game_state.clone();
+        let stop = self.stop.clone();
+        let result = self.result.clone();
+        thread::spawn(move || analyze(game_state, stop, result));
+        status
+    }
+}
+
+// Deepens the search until it is stopped, the depth is only limited by the
+// program options.
+fn analyze(
+    game_state: GameState,
+    stop: Arc<AtomicBool>,
+    result: Arc<Mutex<Option<AnalysisResult>>>,
+) {
+    let mut search = Search::new(1);
+    search.stop = Some(stop);
+    deepen_lines(
+        &game_state,
+        &mut search,
+        unsafe { MULTI_PV },
+        unsafe { ANALYSIS_DEPTH },
+        |depth, lines| *result.lock().unwrap() = Some(AnalysisResult { depth, lines }),
+    );
+}
+
+#[derive(Component)]
+pub struct AnalysisText;
+
+#[derive(Component)]
+pub struct EvalBarFill;
+
+// The share of the bar filled with the color of White.
+fn white_share(score: f32) -> f32 {
+    1. / (1. + (-score / 400.).exp())
+}
+
+fn line_text(game_state: &GameState, line: &PvLine) -> String {
+    let mut text = format_score(line.score);
+    let mut line_state = game_state.clone();
+    for &(from, to) in line.moves.iter().take(SHOWN_LINE_MOVES) {
+        text += &format!(" {}", san(&line_state, (from, to)));
+        line_state.move_piece(from, to, true);
+    }
+    text
+}
+
+pub fn create_analysis_panel(
+    mut commands: Commands,
+    game_textures: Res<GameTextures>,
+    analysis: Option<Res<Analysis>>,
+) {
+    if analysis.is_none() {
+        return;
+    }
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(10.),
+                    top: Val::Px(70.),
+                    ..Default::default()
+                },
+                size: Size::new(Val::Px(EVAL_BAR_SIZE.0), Val::Px(EVAL_BAR_SIZE.1)),
+                ..Default::default()
+            },
+            color: EVAL_BAR_COLORS.1.into(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        size: Size::new(Val::Percent(100.), Val::Percent(50.)),
+                        ..Default::default()
+                    },
+                    color: EVAL_BAR_COLORS.0.into(),
+                    ..Default::default()
+                })
+                .insert(EvalBarFill);
+        });
+    commands
+        .spawn_bundle(
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: game_textures.font.clone(),
+                    font_size: 16.,
+                    color: Color::WHITE,
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(20. + EVAL_BAR_SIZE.0),
+                    top: Val::Px(70.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+        )
+        .insert(AnalysisText);
+}
+
+pub fn analysis_system(
+    game_state: Res<GameState>,
+    analysis: Option<ResMut<Analysis>>,
+    mut query_text: Query<&mut Text, With<AnalysisText>>,
+    mut query_fill: Query<&mut Style, With<EvalBarFill>>,
+    mut shown: Local<(String, f32)>,
+) {
+    let mut analysis = match analysis {
+        Some(analysis) => analysis,
+        None => return,
+    };
+    // The text is only made again for a new position or a new result.
+    if analysis.game_state.as_ref() != Some(&*game_state) {
+        let status = analysis.start(&game_state);
+        *shown = match status {
+            GameStatus::Checkmate(winner) | GameStatus::KingCaptured(winner) => {
+                let share = match winner {
+                    PieceColor::White => 1.,
+                    PieceColor::Black => 0.,
+                };
+                (status.description(), share)
+            }
+            _ if status.is_game_over() => (status.description(), 0.5),
+            _ => (String::from("Analyzing ..."), 0.5),
+        };
+    }
+    let result = analysis.result.lock().unwrap().take();
+    if let Some(result) = result {
+        let mut text = format!("Depth {}\n", result.depth);
+        for line in result.lines.iter() {
+            text += &format!("{}\n", line_text(&game_state, line));
+        }
+        *shown = (text, white_share(result.lines[0].score));
+    }
+    let (text, share) = &*shown;
+    for mut analysis_text in query_text.iter_mut() {
+        if analysis_text.sections[0].value != *text {
+            analysis_text.sections[0].value = text.clone();
+        }
+    }
+    // White fills the bar from the side of the board where its pieces start.
+    let share = Val::Percent(100. * share);
+    let position = if unsafe { BOARD_FLIPPED } {
+        UiRect {
+            top: Val::Px(0.),
+            ..Default::default()
+        }
+    } else {
+        UiRect {
+            bottom: Val::Px(0.),
+            ..Default::default()
+        }
+    };
+    for mut style in query_fill.iter_mut() {
+        if style.size.height != share || style.position != position {
+            style.size.height = share;
+            style.position = position;
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use bevy::prelude::*;
+
+const MOVING_PIECE_Z: f32 = 1.5;
+
+// All the tweens of a move share one timer. While it runs, neither the player
+// nor the engine can move.
+pub struct Animation {
+    pub duration: f32,
+    pub time_left: f32,
+}
+
+impl Animation {
+    pub fn is_running(&self) -> bool {
+        self.time_left > 0.
+    }
+    fn progress(&self) -> f32 {
+        if self.duration > 0. {
+            (1. - self.time_left / self.duration).clamp(0., 1.)
+        } else {
+            1.
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct MoveAnimation {
+    pub start: Vec3,
+    pub end: Vec3,
+}
+
+// Captured pieces lose their Position and fade out before being despawned.
+#[derive(Component)]
+pub struct FadeOut;
+
+fn ease(t: f32) -> f32 {
+    t * t * (3. - 2. * t)
+}
+
+pub fn animation_system(
+    time: Res<Time>,
+    mut animation: ResMut<Animation>,
+    mut commands: Commands,
+    mut query_moving: Query<(Entity, &MoveAnimation, &mut Transform)>,
+    mut query_fading: Query<(Entity, &mut Sprite), With<FadeOut>>,
+) {
+    animation.time_left = (animation.time_left - time.delta_seconds()).max(0.);
+    let progress = animation.progress();
+    for (entity, move_animation, mut transform) in query_moving.iter_mut() {
+        if progress >= 1. {
+            transform.translation = move_animation.end;
+            commands.entity(entity).remove::<MoveAnimation>();
+        } else {
+            transform.translation = move_animation
+                .start
+                .lerp(move_animation.end, ease(progress));
+            transform.translation.z = MOVING_PIECE_Z;
+        }
+    }
+    for (entity, mut sprite) in query_fading.iter_mut() {
+        if progress >= 1. {
+            commands.entity(entity).despawn();
+        } else {
+            sprite.color.set_a(1. - progress);
+        }
+    }
+}
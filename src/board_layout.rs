@@ -0,0 +1,129 @@
+use crate::animation::*;
+use crate::common::*;
+use crate::game_textures::*;
+use crate::hint::*;
+use bevy::prelude::*;
+
+pub const LABEL_FONT_SIZE: f32 = 24.;
+const LABEL_MARGIN: f32 = 30.;
+// Room in pixels left around the board for the buttons, clocks and side panel.
+const HORIZONTAL_SPACE: f32 = 2. * 260.;
+const VERTICAL_SPACE: f32 = 2. * 50.;
+
+#[derive(Debug, Clone, Copy, Component)]
+pub enum BoardLabel {
+    File(i8),
+    Rank(i8),
+}
+
+impl BoardLabel {
+    pub fn text(&self) -> String {
+        match *self {
+            BoardLabel::File(file) => ((b'a' + file as u8) as char).to_string(),
+            BoardLabel::Rank(rank) => (rank + 1).to_string(),
+        }
+    }
+    // Files are written below the board and ranks to the left of it.
+    pub fn translation(&self) -> Vec3 {
+        let edge = (-4. * IMAGE_SIZE.0 - LABEL_MARGIN / 2.) * unsafe { BOARD_SCALE };
+        match *self {
+            BoardLabel::File(file) => Vec3::new(real_position(Position(file, 0)).x, edge, 0.),
+            BoardLabel::Rank(rank) => Vec3::new(edge, real_position(Position(0, rank)).y, 0.),
+        }
+    }
+}
+
+// Scales the board with its labels so that it fills the window. The board is
+// centered on the origin, so the sprites on it are scaled around the origin
+// too. The new sprites are placed with the current scale but spawned with the
+// size of the textures.
+pub fn fit_board_system(
+    windows: Res<Windows>,
+    mut query: Query<
+        (&mut Transform, ChangeTrackers<Transform>),
+        Or<(
+            With<Position>,
+            With<BoardSquare>,
+            With<BoardLabel>,
+            With<HintArrow>,
+            With<FadeOut>,
+        )>,
+    >,
+    mut query_animations: Query<&mut MoveAnimation>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let available_size = (window.width() - HORIZONTAL_SPACE)
+        .min(window.height() - VERTICAL_SPACE)
+        .max(100.);
+    let old_scale = unsafe { BOARD_SCALE };
+    let scale = available_size / (8. * IMAGE_SIZE.0 + 2. * LABEL_MARGIN);
+    let ratio = scale / old_scale;
+    unsafe {
+        BOARD_SCALE = scale;
+    }
+    for (mut transform, tracker) in query.iter_mut() {
+        if tracker.is_added() {
+            transform.scale *= old_scale;
+        }
+        if ratio != 1. {
+            transform.translation *= Vec3::new(ratio, ratio, 1.);
+            transform.scale *= ratio;
+        }
+    }
+    if ratio != 1. {
+        for mut move_animation in query_animations.iter_mut() {
+            move_animation.start *= Vec3::new(ratio, ratio, 1.);
+            move_animation.end *= Vec3::new(ratio, ratio, 1.);
+        }
+    }
+}
+
+pub fn flip_board_system(
+    keys: Res<Input<KeyCode>>,
+    animation: Res<Animation>,
+    mut commands: Commands,
+    query_arrows: Query<Entity, With<HintArrow>>,
+) {
+    if !(ctrl_pressed(&keys) && keys.just_pressed(KeyCode::F)) || animation.is_running() {
+        return;
+    }
+    unsafe {
+        BOARD_FLIPPED = !BOARD_FLIPPED;
+    }
+    for entity in query_arrows.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+// Moves the pieces, squares and labels whenever the board gets flipped, by the
+// player or by starting a game or a puzzle with the other color.
+pub fn board_orientation_system(
+    animation: Res<Animation>,
+    mut query: Query<(
+        &mut Transform,
+        Option<&Position>,
+        Option<&BoardSquare>,
+        Option<&BoardLabel>,
+    )>,
+    mut is_flipped: Local<bool>,
+) {
+    if *is_flipped == unsafe { BOARD_FLIPPED } || animation.is_running() {
+        return;
+    }
+    *is_flipped = unsafe { BOARD_FLIPPED };
+    for (mut transform, position, board_square, label) in query.iter_mut() {
+        let translation = if let Some(position) = position {
+            real_piece_position(*position)
+        } else if let Some(board_square) = board_square {
+            real_position(board_square.position)
+        } else if let Some(label) = label {
+            label.translation()
+        } else {
+            continue;
+        };
+        transform.translation = translation.truncate().extend(transform.translation.z);
+    }
+}
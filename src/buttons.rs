@@ -0,0 +1,103 @@
+use crate::game_textures::*;
+use bevy::prelude::*;
+
+const BUTTON_COLOR: Color = Color::rgb(0.25, 0.25, 0.25);
+const HOVERED_BUTTON_COLOR: Color = Color::rgb(0.35, 0.35, 0.35);
+const PRESSED_BUTTON_COLOR: Color = Color::rgb(0.45, 0.45, 0.45);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub enum ActionButton {
+    Undo,
+    Redo,
+    Hint,
+    NewGame,
+    Review,
+}
+
+const ACTION_BUTTONS: [ActionButton; 5] = [
+    ActionButton::Undo,
+    ActionButton::Redo,
+    ActionButton::Hint,
+    ActionButton::NewGame,
+    ActionButton::Review,
+];
+
+impl ActionButton {
+    fn label(&self) -> &'static str {
+        match self {
+            ActionButton::Undo => "Undo",
+            ActionButton::Redo => "Redo",
+            ActionButton::Hint => "Hint",
+            ActionButton::NewGame => "New game",
+            ActionButton::Review => "Review",
+        }
+    }
+}
+
+pub fn is_clicked(
+    query_buttons: &Query<(&Interaction, &ActionButton), Changed<Interaction>>,
+    action_button: ActionButton,
+) -> bool {
+    query_buttons.iter().any(|(interaction, button)| {
+        *interaction == Interaction::Clicked && *button == action_button
+    })
+}
+
+pub fn create_buttons(mut commands: Commands, game_textures: Res<GameTextures>) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(10.),
+                    bottom: Val::Px(10.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            for action_button in ACTION_BUTTONS {
+                parent
+                    .spawn_bundle(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(90.), Val::Px(30.)),
+                            margin: UiRect::all(Val::Px(5.)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        color: BUTTON_COLOR.into(),
+                        ..Default::default()
+                    })
+                    .insert(action_button)
+                    .with_children(|parent| {
+                        parent.spawn_bundle(TextBundle::from_section(
+                            action_button.label(),
+                            TextStyle {
+                                font: game_textures.font.clone(),
+                                font_size: 18.,
+                                color: Color::WHITE,
+                            },
+                        ));
+                    });
+            }
+        });
+}
+
+pub fn button_color_system(
+    mut query_buttons: Query<
+        (&Interaction, &mut UiColor),
+        (Changed<Interaction>, With<ActionButton>),
+    >,
+) {
+    for (interaction, mut color) in query_buttons.iter_mut() {
+        *color = match *interaction {
+            Interaction::Clicked => PRESSED_BUTTON_COLOR.into(),
+            Interaction::Hovered => HOVERED_BUTTON_COLOR.into(),
+            Interaction::None => BUTTON_COLOR.into(),
+        };
+    }
+}
@@ -0,0 +1,277 @@
+use crate::common::*;
+use crate::game_state::*;
+use crate::game_status::*;
+use crate::game_textures::*;
+use crate::piece::*;
+use bevy::prelude::*;
+use std::time::{Duration, Instant};
+
+const ACTIVE_CLOCK_COLOR: Color = Color::WHITE;
+const INACTIVE_CLOCK_COLOR: Color = Color::rgb(0.5, 0.5, 0.5);
+
+#[derive(Debug, Clone, Copy)]
+pub enum TimeControl {
+    Increment { base: Duration, increment: Duration },
+    PerMove(Duration),
+}
+
+impl TimeControl {
+    // Accepts "m+s" (m minutes with an increment of s seconds) or "Ns"
+    // (N seconds for every move).
+    pub fn parse(text: &str) -> Option<TimeControl> {
+        if let Some((base, increment)) = text.split_once('+') {
+            let base = base.parse::<f64>().ok()?;
+            let increment = increment.parse::<f64>().ok()?;
+            if base <= 0. || increment < 0. {
+                return None;
+            }
+            Some(TimeControl::Increment {
+                base: Duration::from_secs_f64(60. * base),
+                increment: Duration::from_secs_f64(increment),
+            })
+        } else {
+            let per_move = text.strip_suffix('s')?.parse::<f64>().ok()?;
+            if per_move <= 0. {
+                return None;
+            }
+            Some(TimeControl::PerMove(Duration::from_secs_f64(per_move)))
+        }
+    }
+    fn initial_time(&self) -> Duration {
+        match *self {
+            TimeControl::Increment { base, .. } => base,
+            TimeControl::PerMove(per_move) => per_move,
+        }
+    }
+}
+
+// The clock measures real time rather than frame time, because the engine
+// blocks the frame in which it thinks. It starts with the first move.
+pub struct Clock {
+    pub time_control: TimeControl,
+    pub white_time: Duration,
+    pub black_time: Duration,
+    pub flagged: Option<PieceColor>,
+    now_moves: PieceColor,
+    last_update: Option<Instant>,
+}
+
+#[derive(Component)]
+pub struct ClockText {
+    piece_color: PieceColor,
+}
+
+impl Clock {
+    pub fn new(time_control: TimeControl, now_moves: PieceColor) -> Clock {
+        Clock {
+            time_control,
+            white_time: time_control.initial_time(),
+            black_time: time_control.initial_time(),
+            flagged: None,
+            now_moves,
+            last_update: None,
+        }
+    }
+    // The times of White and Black.
+    pub fn times(&self) -> (Duration, Duration) {
+        (self.white_time, self.black_time)
+    }
+    // Sets the times after an undo or a redo, the clock is stopped again when
+    // no move is left.
+    pub fn restore(
+        &mut self,
+        times: (Duration, Duration),
+        now_moves: PieceColor,
+        is_started: bool,
+    ) {
+        (self.white_time, self.black_time) = times;
+        self.flagged = None;
+        self.now_moves = now_moves;
+        self.last_update = is_started.then(Instant::now);
+    }
+    pub fn time_left(&self, piece_color: PieceColor) -> Duration {
+        match piece_color {
+            PieceColor::White => self.white_time,
+            PieceColor::Black => self.black_time,
+        }
+    }
+    fn time_left_mut(&mut self, piece_color: PieceColor) -> &mut Duration {
+        match piece_color {
+            PieceColor::White => &mut self.white_time,
+            PieceColor::Black => &mut self.black_time,
+        }
+    }
+    // How long the engine may think about its next move.
+    pub fn time_budget(&self, piece_color: PieceColor) -> Duration {
+        let time_left = self.time_left(piece_color);
+        match self.time_control {
+            TimeControl::Increment { increment, .. } => {
+                (time_left / 30 + increment * 3 / 4).min(time_left / 2)
+            }
+            TimeControl::PerMove(_) => time_left * 3 / 4,
+        }
+    }
+    fn update(&mut self, now_moves: PieceColor) {
+        let now = Instant::now();
+        let last_update = match self.last_update {
+            Some(last_update) => last_update,
+            None => {
+                if now_moves != self.now_moves {
+                    self.now_moves = now_moves;
+                    self.last_update = Some(now);
+                }
+                return;
+            }
+        };
+        let elapsed = now - last_update;
+        self.last_update = Some(now);
+        if self.flagged.is_some() {
+            return;
+        }
+        let moved = self.now_moves;
+        let time_left = self.time_left(moved).saturating_sub(elapsed);
+        *self.time_left_mut(moved) = time_left;
+        if time_left.is_zero() {
+            self.flagged = Some(moved);
+            return;
+        }
+        if now_moves == moved {
+            return;
+        }
+        match self.time_control {
+            TimeControl::Increment { increment, .. } => *self.time_left_mut(moved) += increment,
+            TimeControl::PerMove(per_move) => *self.time_left_mut(now_moves) = per_move,
+        }
+        self.now_moves = now_moves;
+    }
+}
+
+fn format_time(time: Duration) -> String {
+    let seconds = time.as_secs();
+    if seconds < 10 {
+        format!("0:{:02}.{}", seconds, time.subsec_millis() / 100)
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+// The clock of the side at the bottom of the board is at the bottom.
+fn clock_position(piece_color: PieceColor) -> UiRect<Val> {
+    let is_bottom = (piece_color == PieceColor::White) != unsafe { BOARD_FLIPPED };
+    if is_bottom {
+        UiRect {
+            right: Val::Px(10.),
+            bottom: Val::Px(10.),
+            ..Default::default()
+        }
+    } else {
+        UiRect {
+            right: Val::Px(10.),
+            top: Val::Px(10.),
+            ..Default::default()
+        }
+    }
+}
+
+pub fn create_clocks(
+    mut commands: Commands,
+    game_textures: Res<GameTextures>,
+    clock: Option<Res<Clock>>,
+) {
+    if clock.is_none() {
+        return;
+    }
+    for piece_color in [PieceColor::White, PieceColor::Black] {
+        commands
+            .spawn_bundle(
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: game_textures.font.clone(),
+                        font_size: 40.,
+                        color: INACTIVE_CLOCK_COLOR,
+                    },
+                )
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    position: clock_position(piece_color),
+                    ..Default::default()
+                }),
+            )
+            .insert(ClockText { piece_color });
+    }
+}
+
+pub fn clock_system(
+    clock: Option<ResMut<Clock>>,
+    game_state: Res<GameState>,
+    mut query_text: Query<(&ClockText, &mut Text, &mut Style)>,
+    mut board_status: Local<Option<GameStatus>>,
+) {
+    let mut clock = match clock {
+        Some(clock) => clock,
+        None => return,
+    };
+    if game_state.is_changed() || board_status.is_none() {
+        *board_status = Some(GameStatus::of(&game_state, None));
+    }
+    let status = board_status.unwrap().with_clock(Some(&clock));
+    if !status.is_game_over() {
+        clock.update(game_state.now_moves);
+    }
+    for (clock_text, mut text, mut style) in query_text.iter_mut() {
+        // The board may have been flipped since the clocks were created.
+        let position = clock_position(clock_text.piece_color);
+        if style.position != position {
+            style.position = position;
+        }
+        text.sections[0].value = format_time(clock.time_left(clock_text.piece_color));
+        text.sections[0].style.color = if clock_text.piece_color == game_state.now_moves {
+            ACTIVE_CLOCK_COLOR
+        } else {
+            INACTIVE_CLOCK_COLOR
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_increment() {
+        match TimeControl::parse("5+3") {
+            Some(TimeControl::Increment { base, increment }) => {
+                assert_eq!(base, Duration::from_secs(300));
+                assert_eq!(increment, Duration::from_secs(3));
+            }
+            time_control => panic!("unexpected {:?}", time_control),
+        }
+        match TimeControl::parse("0.5+0") {
+            Some(TimeControl::Increment { base, increment }) => {
+                assert_eq!(base, Duration::from_secs(30));
+                assert!(increment.is_zero());
+            }
+            time_control => panic!("unexpected {:?}", time_control),
+        }
+    }
+
+    #[test]
+    fn parse_per_move() {
+        match TimeControl::parse("10s") {
+            Some(TimeControl::PerMove(per_move)) => {
+                assert_eq!(per_move, Duration::from_secs(10))
+            }
+            time_control => panic!("unexpected {:?}", time_control),
+        }
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for text in [
+            "", "5", "s", "0s", "-1s", "0+2", "5+-1", "a+1", "5+", "+3", "10m",
+        ] {
+            assert!(TimeControl::parse(text).is_none(), "{}", text);
+        }
+    }
+}
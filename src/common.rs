@@ -1,5 +1,6 @@
 use crate::game_textures::*;
 use bevy::prelude::*;
+use rand::rngs::StdRng;
 
 pub const INFINITY: f32 = 1000000.;
 pub const BIG_INFINITY: f32 = 10. * INFINITY;
@@ -14,16 +15,39 @@ pub struct MainCamera;
 
 pub struct MousePosition {
     pub position: Option<Position>,
+    pub world_position: Option<Vec2>,
 }
 
 pub struct SelectedSquare {
     pub position: Option<Position>,
 }
 
+pub struct DraggedPiece {
+    pub entity: Option<Entity>,
+}
+
+pub struct GameRng {
+    pub rng: StdRng,
+}
+
+pub static mut BOARD_FLIPPED: bool = false;
+// The board grows and shrinks with the window, see fit_board_system.
+pub static mut BOARD_SCALE: f32 = 1.;
+
+fn view_position(position: Position) -> Position {
+    if unsafe { BOARD_FLIPPED } {
+        Position(7 - position.0, 7 - position.1)
+    } else {
+        position
+    }
+}
+
 pub fn real_position(position: Position) -> Vec3 {
+    let position = view_position(position);
+    let scale = unsafe { BOARD_SCALE };
     Vec3::new(
-        (position.0 as f32 - 3.5) * IMAGE_SIZE.0,
-        (position.1 as f32 - 3.5) * IMAGE_SIZE.1,
+        (position.0 as f32 - 3.5) * IMAGE_SIZE.0 * scale,
+        (position.1 as f32 - 3.5) * IMAGE_SIZE.1 * scale,
         0.,
     )
 }
@@ -33,7 +57,8 @@ pub fn real_piece_position(position: Position) -> Vec3 {
 }
 
 pub fn game_position(vec: Vec3) -> Option<Position> {
-    let (mut x, mut y) = (vec.x, vec.y);
+    let scale = unsafe { BOARD_SCALE };
+    let (mut x, mut y) = (vec.x / scale, vec.y / scale);
     x += 3.5 * IMAGE_SIZE.0;
     y += 3.5 * IMAGE_SIZE.1;
     x /= IMAGE_SIZE.0;
@@ -43,14 +68,51 @@ pub fn game_position(vec: Vec3) -> Option<Position> {
     if x < 0.0 || y < 0.0 || x >= 8.0 || y >= 8.0 {
         None
     } else {
-        Some(Position(x as i8, y as i8))
+        Some(view_position(Position(x as i8, y as i8)))
     }
 }
 
+// The number keys from 1 to 9 followed by 0.
+pub const DIGIT_KEYS: [KeyCode; 10] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Key0,
+];
+
+pub fn ctrl_pressed(keys: &Input<KeyCode>) -> bool {
+    keys.pressed(KeyCode::LControl) || keys.pressed(KeyCode::RControl)
+}
+
+// The highlight layers, each one is cleared independently of the others.
 #[derive(Component)]
-pub struct Highlight {}
+pub struct LastMoveHighlight;
+
+#[derive(Component)]
+pub struct SelectionHighlight;
+
+#[derive(Component)]
+pub struct TargetHighlight;
+
+#[derive(Component)]
+pub struct CheckHighlight;
+
+#[derive(Component)]
+pub struct Tile {}
+
+// Sprites drawn on a square of the board, other than the pieces.
+#[derive(Component)]
+pub struct BoardSquare {
+    pub position: Position,
+}
 
-pub fn delete_highlight(commands: &mut Commands, query: &Query<Entity, With<Highlight>>) {
+pub fn delete_highlight<T: Component>(commands: &mut Commands, query: &Query<Entity, With<T>>) {
     for entity in query.iter() {
         commands.entity(entity).despawn();
     }
@@ -1,63 +1,139 @@
+use crate::animation::*;
+use crate::clock::*;
 use crate::common::*;
+use crate::difficulty::*;
 use crate::game_state::*;
+use crate::game_status::*;
 use crate::game_textures::*;
-use crate::program_options::*;
+use crate::move_history::*;
+use crate::opening_book::*;
+use crate::puzzle::*;
+use crate::search::*;
+use crate::tablebase::*;
 use bevy::prelude::*;
 use debug_print::debug_println;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use std::collections::HashMap;
+
+fn tablebase_move(game_state: &GameState, rng: &mut StdRng) -> Option<Move> {
+    let good_moves = best_moves(game_state);
+    debug_println!("tablebase moves = {:?}", good_moves);
+    good_moves.choose(rng).copied()
+}
+
+// What the engine based its last move on, shown in the side panel.
+pub enum EngineInfo {
+    Idle,
+    Book,
+    Tablebase,
+    Search { score: f32, depth: i32 },
+}
+
+pub fn format_score(score: f32) -> String {
+    if score >= INFINITY / 4. {
+        String::from("White wins")
+    } else if score <= -INFINITY / 4. {
+        String::from("Black wins")
+    } else {
+        format!("{:+.2}", score / 100.)
+    }
+}
+
+impl EngineInfo {
+    pub fn description(&self) -> Option<String> {
+        match *self {
+            EngineInfo::Idle => None,
+            EngineInfo::Book => Some(String::from("book move")),
+            EngineInfo::Tablebase => Some(String::from("tablebase")),
+            EngineInfo::Search { score, depth } => {
+                Some(format!("{} (depth {})", format_score(score), depth))
+            }
+        }
+    }
+}
+
+// The book is tried first, then the tablebases and finally the search. A move
+// chosen to be a mistake always comes from the search. Shared by all the
+// frontends.
+pub fn choose_computer_move(
+    game_state: &GameState,
+    opening_book: Option<&OpeningBook>,
+    difficulty: &Difficulty,
+    rng: &mut StdRng,
+    engine_info: &mut EngineInfo,
+) -> Option<Move> {
+    let is_mistake = difficulty.makes_mistake(rng);
+    if !is_mistake {
+        let book_move = opening_book.and_then(|book| book.choose_move(game_state, rng));
+        debug_println!("book move = {:?}", book_move);
+        if let Some(book_move) = book_move {
+            *engine_info = EngineInfo::Book;
+            return Some(book_move);
+        }
+        if let Some(tablebase_move) = tablebase_move(game_state, rng) {
+            *engine_info = EngineInfo::Tablebase;
+            return Some(tablebase_move);
+        }
+    }
+    let (lines, depth) = multi_pv(game_state, difficulty, difficulty.lines, rng);
+    let line = difficulty.choose_line(&lines, game_state.now_moves, is_mistake, rng)?;
+    *engine_info = EngineInfo::Search {
+        score: line.score,
+        depth,
+    };
+    line.moves.first().copied()
+}
 
 pub fn computer_moves_system(
-    query_highlight: Query<Entity, With<Highlight>>,
     game_textures: Res<GameTextures>,
+    opening_book: Option<Res<OpeningBook>>,
+    difficulty: Res<Difficulty>,
+    clock: Option<Res<Clock>>,
+    mut game_rng: ResMut<GameRng>,
+    mut engine_info: ResMut<EngineInfo>,
     mut commands: Commands,
     query: Query<(Entity, &mut Position, &mut Transform, &mut Handle<Image>)>,
     mut game_state: ResMut<GameState>,
-    mut app_exit_events: ResMut<Events<bevy::app::AppExit>>,
+    mut move_history: ResMut<MoveHistory>,
+    mut animation: ResMut<Animation>,
+    puzzle_trainer: Option<Res<PuzzleTrainer>>,
 ) {
-    let (white_king, black_king, _is_endgame) = game_state.stats();
-    if !white_king {
-        println!("Black wins!");
-        std::thread::sleep(std::time::Duration::from_millis(1000));
-        app_exit_events.send(bevy::app::AppExit);
-    } else if !black_king {
-        println!("White wins!");
-        std::thread::sleep(std::time::Duration::from_millis(1000));
-        app_exit_events.send(bevy::app::AppExit);
-    } else if !game_state.player_moves {
-        debug_println!("Thinking ...");
-        let mut cache = HashMap::<(GameState, i32), f32>::new();
-        let score: f32;
-        unsafe {
-            score = game_state.evaluate(DEPTH, &mut cache, -BIG_INFINITY, BIG_INFINITY);
-        }
-        let possible_moves = game_state.generate_legal_moves();
-        let good_moves = possible_moves
-            .into_iter()
-            .filter(|(from, to)| {
-                let mut next_state = game_state.clone();
-                next_state.move_piece(*from, *to, true);
-                let next_state_score: f32;
-                unsafe {
-                    next_state_score =
-                        next_state.evaluate(DEPTH - 1, &mut cache, -BIG_INFINITY, BIG_INFINITY);
-                }
-                debug_println!("(move, score) = ({:?}, {:?})", (from, to), next_state_score);
-                score == next_state_score
-            })
-            .collect::<Vec<Move>>();
-        debug_println!("score = {}", score);
-        debug_println!("good moves = {:?}", good_moves);
-        debug_println!("cache size: {}", cache.len());
-        delete_highlight(&mut commands, &query_highlight);
-        let computer_move = good_moves.choose(&mut rand::thread_rng());
-        if let Some(&(from, to)) = computer_move {
-            game_state.computer_move(game_textures, &mut commands, query, from, to);
-            if game_state.player_moves {
-                debug_println!("Your move");
-            }
-        } else {
-            app_exit_events.send(bevy::app::AppExit);
+    // The replies in the puzzles come from their solutions.
+    if animation.is_running() || game_state.player_moves || puzzle_trainer.is_some() {
+        return;
+    }
+    if GameStatus::of(&game_state, clock.as_deref()).is_game_over() {
+        return;
+    }
+    // With a clock the engine never thinks longer than its time budget.
+    let mut difficulty = *difficulty;
+    if let Some(clock) = &clock {
+        let time_budget = clock.time_budget(game_state.now_moves);
+        difficulty.time_limit = Some(match difficulty.time_limit {
+            Some(time_limit) => time_limit.min(time_budget),
+            None => time_budget,
+        });
+    }
+    let computer_move = choose_computer_move(
+        &game_state,
+        opening_book.as_deref(),
+        &difficulty,
+        &mut game_rng.rng,
+        &mut engine_info,
+    );
+    if let Some((from, to)) = computer_move {
+        game_state.computer_move(
+            game_textures,
+            &mut commands,
+            query,
+            &mut move_history,
+            clock.as_deref(),
+            &mut animation,
+            from,
+            to,
+        );
+        if game_state.player_moves {
+            debug_println!("Your move");
         }
     }
 }
@@ -1,3 +1,4 @@
+use crate::board_layout::*;
 use crate::common::*;
 use crate::game_state::*;
 use crate::game_textures::*;
@@ -15,6 +16,25 @@ pub fn create_board(mut commands: Commands, game_textures: Res<GameTextures>) {
             spawn_tile(&mut commands, tile.clone(), Position(i, j), false);
         }
     }
+    for i in 0..8 {
+        for label in [BoardLabel::File(i), BoardLabel::Rank(i)] {
+            commands
+                .spawn_bundle(Text2dBundle {
+                    text: Text::from_section(
+                        label.text(),
+                        TextStyle {
+                            font: game_textures.font.clone(),
+                            font_size: LABEL_FONT_SIZE,
+                            color: Color::WHITE,
+                        },
+                    )
+                    .with_alignment(TextAlignment::CENTER),
+                    transform: Transform::from_translation(label.translation()),
+                    ..Default::default()
+                })
+                .insert(label);
+        }
+    }
 }
 
 pub fn create_pieces(
@@ -22,116 +42,20 @@ pub fn create_pieces(
     game_textures: Res<GameTextures>,
     mut game_state: ResMut<GameState>,
 ) {
-    spawn_piece(
-        &mut commands,
-        game_textures.kingl.clone(),
-        Position(4, 0),
-        PieceColor::White,
-        PieceType::King,
-        &mut game_state,
-    );
-    spawn_piece(
-        &mut commands,
-        game_textures.queenl.clone(),
-        Position(3, 0),
-        PieceColor::White,
-        PieceType::Queen,
-        &mut game_state,
-    );
-    for i in &[0, 7] {
-        spawn_piece(
-            &mut commands,
-            game_textures.rookl.clone(),
-            Position(*i, 0),
-            PieceColor::White,
-            PieceType::Rook,
-            &mut game_state,
-        );
-    }
-    for i in &[2, 5] {
-        spawn_piece(
-            &mut commands,
-            game_textures.bishopl.clone(),
-            Position(*i, 0),
-            PieceColor::White,
-            PieceType::Bishop,
-            &mut game_state,
-        );
-    }
-    for i in &[1, 6] {
-        spawn_piece(
-            &mut commands,
-            game_textures.knightl.clone(),
-            Position(*i, 0),
-            PieceColor::White,
-            PieceType::Knight,
-            &mut game_state,
-        );
-    }
-    for i in 0..8 {
-        spawn_piece(
-            &mut commands,
-            game_textures.pawnl.clone(),
-            Position(i, 1),
-            PieceColor::White,
-            PieceType::Pawn,
-            &mut game_state,
-        );
-    }
-    spawn_piece(
-        &mut commands,
-        game_textures.kingd.clone(),
-        Position(4, 7),
-        PieceColor::Black,
-        PieceType::King,
-        &mut game_state,
-    );
-    spawn_piece(
-        &mut commands,
-        game_textures.queend.clone(),
-        Position(3, 7),
-        PieceColor::Black,
-        PieceType::Queen,
-        &mut game_state,
-    );
-    for i in &[0, 7] {
-        spawn_piece(
-            &mut commands,
-            game_textures.rookd.clone(),
-            Position(*i, 7),
-            PieceColor::Black,
-            PieceType::Rook,
-            &mut game_state,
-        );
-    }
-    for i in &[2, 5] {
-        spawn_piece(
-            &mut commands,
-            game_textures.bishopd.clone(),
-            Position(*i, 7),
-            PieceColor::Black,
-            PieceType::Bishop,
-            &mut game_state,
-        );
-    }
-    for i in &[1, 6] {
-        spawn_piece(
-            &mut commands,
-            game_textures.knightd.clone(),
-            Position(*i, 7),
-            PieceColor::Black,
-            PieceType::Knight,
-            &mut game_state,
-        );
-    }
-    for i in 0..8 {
-        spawn_piece(
-            &mut commands,
-            game_textures.pawnd.clone(),
-            Position(i, 6),
-            PieceColor::Black,
-            PieceType::Pawn,
-            &mut game_state,
+    spawn_pieces(&mut commands, &game_textures, &mut game_state);
+}
+
+pub fn spawn_pieces(
+    commands: &mut Commands,
+    game_textures: &GameTextures,
+    game_state: &mut GameState,
+) {
+    game_state.board = starting_board();
+    for piece in game_state.board.iter().flatten().flatten() {
+        spawn_piece_sprite(
+            commands,
+            game_textures.piece_texture(piece.piece_color, piece.piece_type),
+            Position(piece.x, piece.y),
         );
     }
 }
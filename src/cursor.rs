@@ -21,5 +21,6 @@ pub fn cursor_position_system(
         let world_position = normalized_device_coordinates_to_world
             .project_point3(normalized_device_coordinates.extend(-1.0));
         mouse_position.position = game_position(world_position);
+        mouse_position.world_position = Some(world_position.truncate());
     }
 }
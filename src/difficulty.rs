@@ -0,0 +1,190 @@
+use crate::common::*;
+use crate::eval_params::*;
+use crate::game_textures::*;
+use crate::piece::*;
+use crate::search::*;
+use crate::theme::*;
+use bevy::prelude::*;
+use debug_print::debug_println;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::time::Duration;
+
+pub const MIN_LEVEL: i32 = 1;
+pub const MAX_LEVEL: i32 = 10;
+
+// (depth, time limit in milliseconds, evaluation noise, mistake probability,
+// maximal score loss of a mistake, number of best lines a mistake is chosen
+// from) for every level.
+const LEVELS: [(i32, u64, f32, f64, f32, usize); 10] = [
+    (1, 100, 200., 0.5, 1000., 8),
+    (2, 200, 150., 0.4, 600., 8),
+    (2, 300, 100., 0.3, 400., 6),
+    (3, 500, 75., 0.25, 300., 6),
+    (3, 750, 50., 0.2, 200., 5),
+    (4, 1000, 35., 0.15, 150., 4),
+    (4, 1500, 20., 0.1, 100., 4),
+    (5, 2000, 10., 0.05, 50., 3),
+    (6, 3000, 5., 0.02, 25., 2),
+    (7, 5000, 0., 0., 0., 1),
+];
+
+#[derive(Clone, Copy)]
+pub struct Difficulty {
+    pub level: Option<i32>,
+    pub depth: i32,
+    pub time_limit: Option<Duration>,
+    pub noise: f32,
+    pub mistake_probability: f64,
+    pub max_mistake_loss: f32,
+    pub lines: usize,
+    pub eval_params: EvalParams,
+}
+
+#[derive(Component)]
+pub struct DifficultyMenu;
+
+impl Difficulty {
+    pub fn from_level(level: i32) -> Difficulty {
+        let level = level.clamp(MIN_LEVEL, MAX_LEVEL);
+        let (depth, time_limit, noise, mistake_probability, max_mistake_loss, lines) =
+            LEVELS[(level - MIN_LEVEL) as usize];
+        Difficulty {
+            level: Some(level),
+            depth,
+            time_limit: Some(Duration::from_millis(time_limit)),
+            noise,
+            mistake_probability,
+            max_mistake_loss,
+            lines,
+            eval_params: EvalParams::default(),
+        }
+    }
+    pub fn from_depth(depth: i32) -> Difficulty {
+        Difficulty {
+            level: None,
+            depth,
+            time_limit: None,
+            noise: 0.,
+            mistake_probability: 0.,
+            max_mistake_loss: 0.,
+            lines: 1,
+            eval_params: EvalParams::default(),
+        }
+    }
+    // Decided before the book and the tablebases are asked, so that the weaker
+    // levels make their mistakes in the openings and the endings too.
+    pub fn makes_mistake<R: Rng>(&self, rng: &mut R) -> bool {
+        rng.gen_bool(self.mistake_probability)
+    }
+    // The lines of multi_pv with the given number of lines, the best one
+    // first.
+    pub fn choose_line<'a, R: Rng>(
+        &self,
+        lines: &'a [PvLine],
+        piece_color: PieceColor,
+        is_mistake: bool,
+        rng: &mut R,
+    ) -> Option<&'a PvLine> {
+        let best_score = lines.first()?.score;
+        let loss = |line: &PvLine| match piece_color {
+            PieceColor::White => best_score - line.score,
+            PieceColor::Black => line.score - best_score,
+        };
+        if is_mistake {
+            let mistakes = lines
+                .iter()
+                .filter(|line| loss(line) > 0. && loss(line) <= self.max_mistake_loss)
+                .collect::<Vec<&PvLine>>();
+            if let Some(mistake) = mistakes.choose(rng) {
+                return Some(*mistake);
+            }
+        }
+        let best_lines = lines
+            .iter()
+            .filter(|line| loss(line) <= 0.)
+            .collect::<Vec<&PvLine>>();
+        best_lines.choose(rng).copied()
+    }
+}
+
+fn menu_text(difficulty: &Difficulty) -> String {
+    let mut text = String::from("Difficulty (Ctrl+D to close)\n");
+    for level in MIN_LEVEL..=MAX_LEVEL {
+        let marker = if difficulty.level == Some(level) {
+            ">"
+        } else {
+            " "
+        };
+        let (depth, time_limit, _, _, _, _) = LEVELS[(level - MIN_LEVEL) as usize];
+        text += &format!(
+            "{} {} - level {} (depth {}, {} ms)\n",
+            marker,
+            level % 10,
+            level,
+            depth,
+            time_limit
+        );
+    }
+    if difficulty.level.is_none() {
+        text += &format!("> current: depth {}\n", difficulty.depth);
+    }
+    text
+}
+
+pub fn difficulty_menu_system(
+    keys: Res<Input<KeyCode>>,
+    game_textures: Res<GameTextures>,
+    mut commands: Commands,
+    mut difficulty: ResMut<Difficulty>,
+    query_menu: Query<Entity, With<DifficultyMenu>>,
+    query_other_menu: Query<Entity, With<ThemeMenu>>,
+) {
+    let is_open = !query_menu.is_empty();
+    let mut close = is_open && keys.just_pressed(KeyCode::Escape);
+    if ctrl_pressed(&keys) && keys.just_pressed(KeyCode::D) {
+        if is_open {
+            close = true;
+        } else {
+            // Only one menu at a time takes the digits.
+            for entity in query_other_menu.iter() {
+                commands.entity(entity).despawn();
+            }
+            commands
+                .spawn_bundle(
+                    TextBundle::from_section(
+                        menu_text(&difficulty),
+                        TextStyle {
+                            font: game_textures.font.clone(),
+                            font_size: 20.,
+                            color: Color::WHITE,
+                        },
+                    )
+                    .with_style(Style {
+                        position_type: PositionType::Absolute,
+                        position: UiRect {
+                            left: Val::Px(10.),
+                            top: Val::Px(10.),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    }),
+                )
+                .insert(DifficultyMenu);
+        }
+    }
+    if is_open {
+        for (i, key) in DIGIT_KEYS.iter().enumerate() {
+            if keys.just_pressed(*key) {
+                *difficulty = Difficulty::from_level(MIN_LEVEL + i as i32);
+                debug_println!("Difficulty level {}", MIN_LEVEL + i as i32);
+                close = true;
+            }
+        }
+    }
+    if close {
+        for entity in query_menu.iter() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -0,0 +1,207 @@
+// Wins, draws and losses from the point of view of the first engine.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MatchScore {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+const MIN_SCORE: f64 = 0.001;
+// The variance of a game result is at least this, so that a run of equal
+// results, like only draws, still moves the log likelihood ratio to a bound.
+const MIN_VARIANCE: f64 = 0.01;
+
+pub fn elo_from_score(score: f64) -> f64 {
+    let score = score.clamp(MIN_SCORE, 1. - MIN_SCORE);
+    -400. * (1. / score - 1.).log10()
+}
+
+pub fn score_from_elo(elo: f64) -> f64 {
+    1. / (1. + 10f64.powf(-elo / 400.))
+}
+
+impl MatchScore {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+    pub fn add(&mut self, score: f64) {
+        if score > 0.5 {
+            self.wins += 1;
+        } else if score < 0.5 {
+            self.losses += 1;
+        } else {
+            self.draws += 1;
+        }
+    }
+    // The average result of a game, with a win worth 1 and a draw 0.5.
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + 0.5 * self.draws as f64) / self.games().max(1) as f64
+    }
+    // The variance of the result of a single game.
+    pub fn variance(&self) -> f64 {
+        let score = self.score();
+        let games = self.games().max(1) as f64;
+        (self.wins as f64 * (1. - score).powi(2)
+            + self.draws as f64 * (0.5 - score).powi(2)
+            + self.losses as f64 * score.powi(2))
+            / games
+    }
+    // The Elo difference with the half width of its 95% confidence interval.
+    pub fn elo(&self) -> (f64, f64) {
+        let score = self.score();
+        let margin = 1.96 * (self.variance() / self.games().max(1) as f64).sqrt();
+        let low = elo_from_score(score - margin);
+        let high = elo_from_score(score + margin);
+        (elo_from_score(score), (high - low) / 2.)
+    }
+    // The log likelihood ratio of the Elo difference being elo1 rather than
+    // elo0, in the normal approximation of the game results.
+    pub fn log_likelihood_ratio(&self, elo0: f64, elo1: f64) -> f64 {
+        let variance = self.variance().max(MIN_VARIANCE);
+        let score0 = score_from_elo(elo0);
+        let score1 = score_from_elo(elo1);
+        self.games() as f64 * (score1 - score0) * (2. * self.score() - score0 - score1)
+            / (2. * variance)
+    }
+}
+
+pub enum SprtResult {
+    Continue,
+    AcceptH0,
+    AcceptH1,
+}
+
+// A sequential probability ratio test of H0: Elo difference = elo0 against
+// H1: Elo difference = elo1 with the given error probabilities.
+#[derive(Clone, Copy)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Sprt {
+    // "elo0,elo1" or "elo0,elo1,alpha,beta", alpha and beta default to 0.05.
+    pub fn parse(text: &str) -> Option<Sprt> {
+        let values = text
+            .split(',')
+            .map(|value| value.trim().parse::<f64>().ok())
+            .collect::<Option<Vec<f64>>>()?;
+        let (elo0, elo1, alpha, beta) = match values[..] {
+            [elo0, elo1] => (elo0, elo1, 0.05, 0.05),
+            [elo0, elo1, alpha, beta] => (elo0, elo1, alpha, beta),
+            _ => return None,
+        };
+        let is_probability = |p: f64| p > 0. && p < 0.5;
+        if elo0 >= elo1 || !is_probability(alpha) || !is_probability(beta) {
+            return None;
+        }
+        Some(Sprt {
+            elo0,
+            elo1,
+            alpha,
+            beta,
+        })
+    }
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1. - self.alpha)).ln(),
+            ((1. - self.beta) / self.alpha).ln(),
+        )
+    }
+    pub fn test(&self, score: &MatchScore) -> SprtResult {
+        let llr = score.log_likelihood_ratio(self.elo0, self.elo1);
+        let (lower, upper) = self.bounds();
+        if llr <= lower {
+            SprtResult::AcceptH0
+        } else if llr >= upper {
+            SprtResult::AcceptH1
+        } else {
+            SprtResult::Continue
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(value: f64, expected: f64) {
+        assert!(
+            (value - expected).abs() < 1e-6,
+            "{} is not {}",
+            value,
+            expected
+        );
+    }
+
+    fn match_score(wins: u32, draws: u32, losses: u32) -> MatchScore {
+        MatchScore {
+            wins,
+            draws,
+            losses,
+        }
+    }
+
+    #[test]
+    fn score_and_variance() {
+        let score = match_score(60, 20, 20);
+        assert_close(score.score(), 0.7);
+        // (60 * 0.3^2 + 20 * 0.2^2 + 20 * 0.7^2) / 100
+        assert_close(score.variance(), 0.16);
+    }
+
+    #[test]
+    fn elo() {
+        let (elo, margin) = match_score(60, 20, 20).elo();
+        // -400 * log10(1 / 0.7 - 1)
+        assert_close(elo, 147.190714);
+        // The interval of the score is 0.7 +/- 1.96 * sqrt(0.16 / 100).
+        assert_close(margin, 66.014639);
+        assert_close(match_score(5, 10, 5).elo().0, 0.);
+    }
+
+    #[test]
+    fn log_likelihood_ratio() {
+        // 100 * (s1 - s0) * (2 * 0.7 - s0 - s1) / (2 * 0.16) with s0 = 0.5
+        // and s1 = 1 / (1 + 10^(-10 / 400)).
+        assert_close(
+            match_score(60, 20, 20).log_likelihood_ratio(0., 10.),
+            1.733713,
+        );
+        // Only draws use the minimal variance.
+        assert_close(
+            match_score(0, 100, 0).log_likelihood_ratio(0., 10.),
+            -1.034955,
+        );
+        assert!(match_score(10, 0, 0).log_likelihood_ratio(0., 10.) > 0.);
+    }
+
+    #[test]
+    fn sprt_bounds() {
+        let sprt = Sprt::parse("0,10").unwrap();
+        // ln(0.05 / 0.95) and ln(0.95 / 0.05)
+        assert_close(sprt.bounds().0, -2.944439);
+        assert_close(sprt.bounds().1, 2.944439);
+        let sprt = Sprt::parse("0,10,0.1,0.2").unwrap();
+        // ln(0.2 / 0.9) and ln(0.8 / 0.1)
+        assert_close(sprt.bounds().0, -1.504077);
+        assert_close(sprt.bounds().1, 2.079442);
+        assert!(Sprt::parse("10,0").is_none());
+        assert!(Sprt::parse("0,10,0.5,0.05").is_none());
+    }
+
+    #[test]
+    fn sprt_ends_on_draws() {
+        let sprt = Sprt::parse("0,10").unwrap();
+        assert!(matches!(
+            sprt.test(&match_score(0, 400, 0)),
+            SprtResult::AcceptH0
+        ));
+        assert!(matches!(
+            sprt.test(&match_score(40, 0, 0)),
+            SprtResult::AcceptH1
+        ));
+    }
+}
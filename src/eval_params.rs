@@ -0,0 +1,74 @@
+use crate::common::*;
+use crate::piece::*;
+use std::fs;
+use std::io;
+
+// The material values and the weight of the piece square tables. A parameter
+// file holds lines like "queen 900" or "tables 0.5", the missing parameters
+// keep their default values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EvalParams {
+    pub queen: f32,
+    pub rook: f32,
+    pub bishop: f32,
+    pub knight: f32,
+    pub pawn: f32,
+    pub table_weight: f32,
+}
+
+impl Default for EvalParams {
+    fn default() -> EvalParams {
+        EvalParams {
+            queen: 900.,
+            rook: 500.,
+            bishop: 330.,
+            knight: 320.,
+            pawn: 100.,
+            table_weight: 1.,
+        }
+    }
+}
+
+impl EvalParams {
+    pub fn load(path: &str) -> io::Result<EvalParams> {
+        let mut eval_params = EvalParams::default();
+        for line in fs::read_to_string(path)?.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid_line = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid line: {}", line),
+                )
+            };
+            let (name, value) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(invalid_line)?;
+            let value = value.trim().parse::<f32>().map_err(|_| invalid_line())?;
+            let parameter = match name {
+                "queen" => &mut eval_params.queen,
+                "rook" => &mut eval_params.rook,
+                "bishop" => &mut eval_params.bishop,
+                "knight" => &mut eval_params.knight,
+                "pawn" => &mut eval_params.pawn,
+                "tables" => &mut eval_params.table_weight,
+                _ => return Err(invalid_line()),
+            };
+            *parameter = value;
+        }
+        Ok(eval_params)
+    }
+    pub fn value(&self, piece: &Piece, is_endgame: bool) -> f32 {
+        let value = match piece.piece_type {
+            PieceType::King => INFINITY,
+            PieceType::Queen => self.queen,
+            PieceType::Rook => self.rook,
+            PieceType::Bishop => self.bishop,
+            PieceType::Knight => self.knight,
+            PieceType::Pawn => self.pawn,
+        };
+        piece.signed(value + self.table_weight * piece.table_value(is_endgame))
+    }
+}
@@ -1,11 +1,14 @@
+use crate::animation::*;
+use crate::clock::*;
 use crate::common::*;
 use crate::game_textures::*;
+use crate::move_history::*;
 use crate::physical_board::*;
 use crate::piece::*;
 use crate::program_options::*;
-use crate::spawn_tile::*;
+use crate::search::*;
+use crate::tablebase::*;
 use bevy::prelude::*;
-use std::collections::HashMap;
 
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct GameState {
@@ -14,17 +17,27 @@ pub struct GameState {
     pub player_moves: bool,
 }
 
+pub fn start_position() -> GameState {
+    GameState {
+        board: starting_board(),
+        now_moves: PieceColor::White,
+        player_moves: false,
+    }
+}
+
 impl GameState {
-    pub fn stats(&self) -> (bool, bool, bool) {
+    pub fn stats(&self) -> (bool, bool, bool, usize) {
         let mut white_king = false;
         let mut black_king = false;
         let mut white_queen = 0;
         let mut black_queen = 0;
         let mut white_other_pieces = 0;
         let mut black_other_pieces = 0;
+        let mut pieces = 0;
         for i in 0..8 {
             for j in 0..8 {
                 if let Some(piece) = self.board[i][j] {
+                    pieces += 1;
                     match (piece.piece_type, piece.piece_color) {
                         (PieceType::King, PieceColor::White) => white_king = true,
                         (PieceType::King, PieceColor::Black) => black_king = true,
@@ -39,54 +52,52 @@ impl GameState {
         }
         let is_endgame = (white_queen == 0 || white_other_pieces <= 1)
             && (black_queen == 0 || black_other_pieces <= 1);
-        (white_king, black_king, is_endgame)
-    }
-    fn cache_insert(
-        &self,
-        level: i32,
-        cache: &mut HashMap<(GameState, i32), f32>,
-        key: (GameState, i32),
-        value: f32,
-    ) {
-        unsafe {
-            if level == DEPTH - 1 || level <= DEPTH - 4 {
-                cache.insert(key, value);
-            }
+        (white_king, black_king, is_endgame, pieces)
+    }
+    fn cache_insert(&self, level: i32, search: &mut Search, key: (GameState, i32), value: f32) {
+        if search.is_aborted {
+            return;
+        }
+        if level == search.depth - 1 || level <= search.depth - 4 {
+            search.cache.insert(key, value);
         }
     }
-    fn evaluate_static(&self, is_endgame: bool) -> f32 {
-        let mut score = 0.;
+    fn evaluate_static(&self, is_endgame: bool, search: &Search) -> f32 {
+        let mut score = search.noise(self);
         for i in 0..8 {
             for j in 0..8 {
                 if let Some(x) = self.board[i][j] {
-                    score += x.value(is_endgame);
+                    score += search.eval_params.value(&x, is_endgame);
                 }
             }
         }
         score
     }
-    pub fn evaluate(
-        &self,
-        level: i32,
-        cache: &mut HashMap<(GameState, i32), f32>,
-        mut alpha: f32,
-        mut beta: f32,
-    ) -> f32 {
+    pub fn evaluate(&self, level: i32, search: &mut Search, mut alpha: f32, mut beta: f32) -> f32 {
+        if search.is_out_of_time() {
+            return 0.;
+        }
         let key = (self.clone(), level);
-        if let Some(x) = cache.get(&key) {
+        if let Some(x) = search.cache.get(&key) {
             *x
         } else {
-            let (white_king, black_king, is_endgame) = self.stats();
+            let (white_king, black_king, is_endgame, pieces) = self.stats();
             if !white_king {
                 let value = -2. * INFINITY - level as f32 / 10.;
-                self.cache_insert(level, cache, key, value);
+                self.cache_insert(level, search, key, value);
                 return value;
             }
             if !black_king {
                 let value = 2. * INFINITY + level as f32 / 10.;
-                self.cache_insert(level, cache, key, value);
+                self.cache_insert(level, search, key, value);
                 return value;
             }
+            if pieces <= MAX_TABLEBASE_PIECES {
+                if let Some(value) = probe_score(self) {
+                    self.cache_insert(level, search, key, value);
+                    return value;
+                }
+            }
             let value = if level > 0 {
                 let mut score = match self.now_moves {
                     PieceColor::White => -BIG_INFINITY,
@@ -95,7 +106,7 @@ impl GameState {
                 for (from, to) in self.generate_legal_moves() {
                     let mut next_state = self.clone();
                     next_state.move_piece(from, to, true);
-                    let next_state_score = next_state.evaluate(level - 1, cache, alpha, beta);
+                    let next_state_score = next_state.evaluate(level - 1, search, alpha, beta);
                     match self.now_moves {
                         PieceColor::White => {
                             score = score.max(next_state_score);
@@ -115,9 +126,9 @@ impl GameState {
                 }
                 score
             } else {
-                self.evaluate_static(is_endgame)
+                self.evaluate_static(is_endgame, search)
             };
-            self.cache_insert(level, cache, key, value);
+            self.cache_insert(level, search, key, value);
             value
         }
     }
@@ -141,6 +152,17 @@ impl GameState {
         });
         legal_moves
     }
+    // The moves which do not leave the own king in check.
+    pub fn generate_safe_moves(&self) -> Vec<Move> {
+        self.generate_legal_moves()
+            .into_iter()
+            .filter(|(from, to)| {
+                let mut next_state = self.clone();
+                next_state.move_piece(*from, *to, true);
+                !next_state.is_in_check(self.now_moves)
+            })
+            .collect()
+    }
     pub fn move_piece(&mut self, from: Position, to: Position, change_now_moves: bool) {
         let mut piece = self.board[from.0 as usize][from.1 as usize].take().unwrap();
         if piece.piece_type == PieceType::King {
@@ -157,10 +179,64 @@ impl GameState {
         piece.move_piece(to.0, to.1);
         self.board[to.0 as usize][to.1 as usize] = Some(piece);
         if change_now_moves {
-            self.now_moves = match self.now_moves {
-                PieceColor::White => PieceColor::Black,
-                PieceColor::Black => PieceColor::White,
-            };
+            self.now_moves = self.now_moves.opposite();
+        }
+    }
+    pub fn king_position(&self, piece_color: PieceColor) -> Option<Position> {
+        for i in 0..8 {
+            for j in 0..8 {
+                if let Some(piece) = self.board[i][j] {
+                    if piece.piece_type == PieceType::King && piece.piece_color == piece_color {
+                        return Some(Position(i as i8, j as i8));
+                    }
+                }
+            }
+        }
+        None
+    }
+    pub fn is_in_check(&self, piece_color: PieceColor) -> bool {
+        let king_position = match self.king_position(piece_color) {
+            Some(position) => position,
+            None => return false,
+        };
+        for i in 0..8 {
+            for j in 0..8 {
+                if let Some(piece) = self.board[i][j] {
+                    if piece.piece_color != piece_color
+                        && piece
+                            .generate_legal_moves(self.board)
+                            .iter()
+                            .any(|(_from, to)| *to == king_position)
+                    {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+    // For every square, the positions of the pieces of the given color attacking it.
+    pub fn attack_map(&self, piece_color: PieceColor) -> [[Vec<Position>; 8]; 8] {
+        let mut attack_map: [[Vec<Position>; 8]; 8] = Default::default();
+        for i in 0..8 {
+            for j in 0..8 {
+                if let Some(piece) = self.board[i][j] {
+                    if piece.piece_color == piece_color {
+                        for Position(x, y) in piece.generate_attacks(self.board) {
+                            attack_map[x as usize][y as usize].push(Position(i as i8, j as i8));
+                        }
+                    }
+                }
+            }
+        }
+        attack_map
+    }
+    pub fn play_move(&mut self, from: Position, to: Position) {
+        self.move_piece(from, to, true);
+        unsafe {
+            if NUMBER_OF_PLAYERS == 1 {
+                self.player_moves = !self.player_moves;
+            }
         }
     }
     fn move_piece_for_real(
@@ -168,18 +244,36 @@ impl GameState {
         mut game_textures: Res<GameTextures>,
         commands: &mut Commands,
         mut query: Query<(Entity, &mut Position, &mut Transform, &mut Handle<Image>)>,
+        move_history: &mut MoveHistory,
+        clock: Option<&Clock>,
+        animation: &mut Animation,
         from: Position,
         to: Position,
     ) {
-        spawn_tile(commands, game_textures.highlight.clone(), from, true);
-        spawn_tile(commands, game_textures.highlight.clone(), to, true);
+        move_history.record(self, (from, to), clock);
         let piece_type = self.board[from.0 as usize][from.1 as usize]
             .unwrap()
             .piece_type;
         if piece_type == PieceType::Pawn && (to.1 == 0 || to.1 == 7) {
-            move_piece_physically(&mut game_textures, commands, &mut query, from, to, true);
+            move_piece_physically(
+                &mut game_textures,
+                commands,
+                &mut query,
+                animation,
+                from,
+                to,
+                true,
+            );
         } else {
-            move_piece_physically(&mut game_textures, commands, &mut query, from, to, false);
+            move_piece_physically(
+                &mut game_textures,
+                commands,
+                &mut query,
+                animation,
+                from,
+                to,
+                false,
+            );
         }
         if piece_type == PieceType::King {
             if from.0 + 2 == to.0 {
@@ -187,6 +281,7 @@ impl GameState {
                     &mut game_textures,
                     commands,
                     &mut query,
+                    animation,
                     Position(7, from.1),
                     Position(5, to.1),
                     false,
@@ -197,41 +292,61 @@ impl GameState {
                     &mut game_textures,
                     commands,
                     &mut query,
+                    animation,
                     Position(0, from.1),
                     Position(3, to.1),
                     false,
                 );
             }
         }
-        self.move_piece(from, to, true);
-        unsafe {
-            if NUMBER_OF_PLAYERS == 1 {
-                self.player_moves = !self.player_moves;
-            }
-        }
+        self.play_move(from, to);
     }
     pub fn player_move(
         &mut self,
         game_textures: Res<GameTextures>,
         commands: &mut Commands,
         query: Query<(Entity, &mut Position, &mut Transform, &mut Handle<Image>)>,
+        move_history: &mut MoveHistory,
+        clock: Option<&Clock>,
+        animation: &mut Animation,
         from: Position,
         to: Position,
     ) {
         if !self.player_moves || !self.generate_legal_moves().contains(&(from, to)) {
             return;
         }
-        self.move_piece_for_real(game_textures, commands, query, from, to);
+        self.move_piece_for_real(
+            game_textures,
+            commands,
+            query,
+            move_history,
+            clock,
+            animation,
+            from,
+            to,
+        );
     }
     pub fn computer_move(
         &mut self,
         game_textures: Res<GameTextures>,
         commands: &mut Commands,
         query: Query<(Entity, &mut Position, &mut Transform, &mut Handle<Image>)>,
+        move_history: &mut MoveHistory,
+        clock: Option<&Clock>,
+        animation: &mut Animation,
         from: Position,
         to: Position,
     ) {
         assert!(!self.player_moves && self.generate_legal_moves().contains(&(from, to)));
-        self.move_piece_for_real(game_textures, commands, query, from, to);
+        self.move_piece_for_real(
+            game_textures,
+            commands,
+            query,
+            move_history,
+            clock,
+            animation,
+            from,
+            to,
+        );
     }
 }
@@ -0,0 +1,109 @@
+use crate::clock::*;
+use crate::game_state::*;
+use crate::piece::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameStatus {
+    Ongoing,
+    Check,
+    Checkmate(PieceColor),
+    Stalemate,
+    InsufficientMaterial,
+    KingCaptured(PieceColor),
+    Timeout(PieceColor),
+    Repetition,
+    FiftyMoves,
+}
+
+fn is_insufficient_material(game_state: &GameState) -> bool {
+    let pieces = game_state
+        .board
+        .iter()
+        .flatten()
+        .flatten()
+        .filter(|piece| piece.piece_type != PieceType::King)
+        .collect::<Vec<&Piece>>();
+    match pieces[..] {
+        [] => true,
+        [piece] => matches!(piece.piece_type, PieceType::Bishop | PieceType::Knight),
+        _ => false,
+    }
+}
+
+fn color_name(piece_color: PieceColor) -> &'static str {
+    match piece_color {
+        PieceColor::White => "White",
+        PieceColor::Black => "Black",
+    }
+}
+
+// The colors in the variants are the winners. The draws by repetition and by
+// the fifty move rule need the history of the game, GameStatus::of never
+// returns them.
+impl GameStatus {
+    pub fn of(game_state: &GameState, clock: Option<&Clock>) -> GameStatus {
+        if let Some(piece_color) = clock.and_then(|clock| clock.flagged) {
+            return GameStatus::Timeout(piece_color.opposite());
+        }
+        let (white_king, black_king, _is_endgame, _pieces) = game_state.stats();
+        if !white_king {
+            return GameStatus::KingCaptured(PieceColor::Black);
+        }
+        if !black_king {
+            return GameStatus::KingCaptured(PieceColor::White);
+        }
+        if is_insufficient_material(game_state) {
+            return GameStatus::InsufficientMaterial;
+        }
+        let is_in_check = game_state.is_in_check(game_state.now_moves);
+        if game_state.generate_safe_moves().is_empty() {
+            if is_in_check {
+                return GameStatus::Checkmate(game_state.now_moves.opposite());
+            }
+            return GameStatus::Stalemate;
+        }
+        if is_in_check {
+            GameStatus::Check
+        } else {
+            GameStatus::Ongoing
+        }
+    }
+    // Adds the flag of the clock to a status computed without it, so that the
+    // systems only compute the status when the position changes.
+    pub fn with_clock(self, clock: Option<&Clock>) -> GameStatus {
+        match clock.and_then(|clock| clock.flagged) {
+            Some(piece_color) => GameStatus::Timeout(piece_color.opposite()),
+            None => self,
+        }
+    }
+    pub fn is_game_over(&self) -> bool {
+        !matches!(self, GameStatus::Ongoing | GameStatus::Check)
+    }
+    // The result written in PGN, a game stopped before its end is a draw.
+    pub fn result(&self) -> &'static str {
+        match *self {
+            GameStatus::Checkmate(PieceColor::White)
+            | GameStatus::KingCaptured(PieceColor::White)
+            | GameStatus::Timeout(PieceColor::White) => "1-0",
+            GameStatus::Checkmate(PieceColor::Black)
+            | GameStatus::KingCaptured(PieceColor::Black)
+            | GameStatus::Timeout(PieceColor::Black) => "0-1",
+            _ => "1/2-1/2",
+        }
+    }
+    pub fn description(&self) -> String {
+        match *self {
+            GameStatus::Ongoing => String::new(),
+            GameStatus::Check => String::from("Check!"),
+            GameStatus::Checkmate(winner) => format!("Checkmate, {} wins", color_name(winner)),
+            GameStatus::Stalemate => String::from("Stalemate, draw"),
+            GameStatus::InsufficientMaterial => String::from("Draw by insufficient material"),
+            GameStatus::KingCaptured(winner) => {
+                format!("King captured, {} wins", color_name(winner))
+            }
+            GameStatus::Timeout(winner) => format!("{} wins on time", color_name(winner)),
+            GameStatus::Repetition => String::from("Draw by threefold repetition"),
+            GameStatus::FiftyMoves => String::from("Draw by the fifty move rule"),
+        }
+    }
+}
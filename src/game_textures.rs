@@ -1,4 +1,6 @@
+use crate::piece::*;
 use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
 
 pub const SCALING_FACTOR: f32 = 1.5;
 pub const IMAGE_SIZE: (f32, f32) = (SCALING_FACTOR * 45., SCALING_FACTOR * 45.);
@@ -17,6 +19,8 @@ pub const BISHOPD_SPRITE: &str = "sprites/bd.png";
 pub const KNIGHTD_SPRITE: &str = "sprites/nd.png";
 pub const PAWND_SPRITE: &str = "sprites/pd.png";
 pub const HIGHLIGHT_SPRITE: &str = "sprites/highlight.png";
+pub const FONT: &str = "fonts/DejaVuSans.ttf";
+const HINT_IMAGE_SIZE: u32 = 45;
 
 pub struct GameTextures {
     pub tilel: Handle<Image>,
@@ -34,4 +38,115 @@ pub struct GameTextures {
     pub knightd: Handle<Image>,
     pub pawnd: Handle<Image>,
     pub highlight: Handle<Image>,
+    pub move_hint: Handle<Image>,
+    pub capture_hint: Handle<Image>,
+    pub arrow_head: Handle<Image>,
+    pub threat_ring: Handle<Image>,
+    pub cursor_frame: Handle<Image>,
+    pub font: Handle<Font>,
+}
+
+impl GameTextures {
+    pub fn piece_texture(&self, piece_color: PieceColor, piece_type: PieceType) -> Handle<Image> {
+        match (piece_color, piece_type) {
+            (PieceColor::White, PieceType::King) => self.kingl.clone(),
+            (PieceColor::White, PieceType::Queen) => self.queenl.clone(),
+            (PieceColor::White, PieceType::Rook) => self.rookl.clone(),
+            (PieceColor::White, PieceType::Bishop) => self.bishopl.clone(),
+            (PieceColor::White, PieceType::Knight) => self.knightl.clone(),
+            (PieceColor::White, PieceType::Pawn) => self.pawnl.clone(),
+            (PieceColor::Black, PieceType::King) => self.kingd.clone(),
+            (PieceColor::Black, PieceType::Queen) => self.queend.clone(),
+            (PieceColor::Black, PieceType::Rook) => self.rookd.clone(),
+            (PieceColor::Black, PieceType::Bishop) => self.bishopd.clone(),
+            (PieceColor::Black, PieceType::Knight) => self.knightd.clone(),
+            (PieceColor::Black, PieceType::Pawn) => self.pawnd.clone(),
+        }
+    }
+    pub fn piece_texture_mut(
+        &mut self,
+        piece_color: PieceColor,
+        piece_type: PieceType,
+    ) -> &mut Handle<Image> {
+        match (piece_color, piece_type) {
+            (PieceColor::White, PieceType::King) => &mut self.kingl,
+            (PieceColor::White, PieceType::Queen) => &mut self.queenl,
+            (PieceColor::White, PieceType::Rook) => &mut self.rookl,
+            (PieceColor::White, PieceType::Bishop) => &mut self.bishopl,
+            (PieceColor::White, PieceType::Knight) => &mut self.knightl,
+            (PieceColor::White, PieceType::Pawn) => &mut self.pawnl,
+            (PieceColor::Black, PieceType::King) => &mut self.kingd,
+            (PieceColor::Black, PieceType::Queen) => &mut self.queend,
+            (PieceColor::Black, PieceType::Rook) => &mut self.rookd,
+            (PieceColor::Black, PieceType::Bishop) => &mut self.bishopd,
+            (PieceColor::Black, PieceType::Knight) => &mut self.knightd,
+            (PieceColor::Black, PieceType::Pawn) => &mut self.pawnd,
+        }
+    }
+}
+
+fn hint_image(color: [u8; 3], is_opaque: impl Fn(f32, f32) -> bool) -> Image {
+    let mut data = Vec::new();
+    for y in 0..HINT_IMAGE_SIZE {
+        for x in 0..HINT_IMAGE_SIZE {
+            let alpha = if is_opaque(x as f32, y as f32) {
+                255
+            } else {
+                0
+            };
+            data.extend_from_slice(&[color[0], color[1], color[2], alpha]);
+        }
+    }
+    Image::new(
+        Extent3d {
+            width: HINT_IMAGE_SIZE,
+            height: HINT_IMAGE_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+// Used for the squares when the texture of a theme is missing.
+pub fn solid_image(color: [u8; 3]) -> Image {
+    hint_image(color, |_, _| true)
+}
+
+fn ring_image(color: u8, inner_radius: f32, outer_radius: f32) -> Image {
+    let center = (HINT_IMAGE_SIZE as f32 - 1.) / 2.;
+    hint_image([color; 3], |x, y| {
+        let distance = (x - center).hypot(y - center);
+        distance >= inner_radius && distance <= outer_radius
+    })
+}
+
+pub fn move_hint_image() -> Image {
+    ring_image(0, 0., 7.)
+}
+
+pub fn capture_hint_image() -> Image {
+    ring_image(0, 18., 22.5)
+}
+
+// A white ring, colored by the sprite using it.
+pub fn threat_ring_image() -> Image {
+    ring_image(255, 18., 22.5)
+}
+
+// A white frame around the whole image, colored by the sprite using it.
+pub fn cursor_frame_image() -> Image {
+    let last = HINT_IMAGE_SIZE as f32 - 1.;
+    hint_image([255; 3], |x, y| {
+        x < 3. || y < 3. || x > last - 3. || y > last - 3.
+    })
+}
+
+// A white triangle pointing to the right, colored by the sprite using it.
+pub fn arrow_head_image() -> Image {
+    let center = (HINT_IMAGE_SIZE as f32 - 1.) / 2.;
+    hint_image([255; 3], |x, y| {
+        (y - center).abs() <= center * (1. - x / (HINT_IMAGE_SIZE as f32 - 1.))
+    })
 }
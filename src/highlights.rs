@@ -0,0 +1,101 @@
+use crate::common::*;
+use crate::game_state::*;
+use crate::game_textures::*;
+use crate::move_history::*;
+use crate::spawn_tile::*;
+use bevy::prelude::*;
+
+const CHECK_COLOR: Color = Color::rgba(1., 0., 0., 0.5);
+
+pub fn last_move_highlight_system(
+    game_textures: Res<GameTextures>,
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    move_history: Res<MoveHistory>,
+    query_last_move: Query<Entity, With<LastMoveHighlight>>,
+) {
+    if !game_state.is_changed() {
+        return;
+    }
+    delete_highlight(&mut commands, &query_last_move);
+    if let Some((_, (from, to))) = move_history.moves.last() {
+        for position in [*from, *to] {
+            let entity = spawn_tile(
+                &mut commands,
+                game_textures.highlight.clone(),
+                position,
+                true,
+            );
+            commands.entity(entity).insert(LastMoveHighlight);
+        }
+    }
+}
+
+pub fn check_highlight_system(
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    query_check: Query<Entity, With<CheckHighlight>>,
+) {
+    if !game_state.is_changed() {
+        return;
+    }
+    delete_highlight(&mut commands, &query_check);
+    if !game_state.is_in_check(game_state.now_moves) {
+        return;
+    }
+    if let Some(position) = game_state.king_position(game_state.now_moves) {
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: CHECK_COLOR,
+                    custom_size: Some(Vec2::new(IMAGE_SIZE.0, IMAGE_SIZE.1)),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(
+                    real_position(position) + Vec3::new(0., 0., 0.55),
+                ),
+                ..Default::default()
+            })
+            .insert(CheckHighlight)
+            .insert(BoardSquare { position });
+    }
+}
+
+// The selected square and the legal targets of its piece.
+pub fn selection_highlight_system(
+    game_textures: Res<GameTextures>,
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    selected_square: Res<SelectedSquare>,
+    query_selection: Query<Entity, With<SelectionHighlight>>,
+    query_targets: Query<Entity, With<TargetHighlight>>,
+) {
+    if !selected_square.is_changed() && !game_state.is_changed() {
+        return;
+    }
+    delete_highlight(&mut commands, &query_selection);
+    delete_highlight(&mut commands, &query_targets);
+    let position = match selected_square.position {
+        Some(position) => position,
+        None => return,
+    };
+    let entity = spawn_tile(
+        &mut commands,
+        game_textures.highlight.clone(),
+        position,
+        true,
+    );
+    commands.entity(entity).insert(SelectionHighlight);
+    for (from, to) in game_state.generate_legal_moves() {
+        if from != position {
+            continue;
+        }
+        let texture = if game_state.board[to.0 as usize][to.1 as usize].is_some() {
+            game_textures.capture_hint.clone()
+        } else {
+            game_textures.move_hint.clone()
+        };
+        let entity = spawn_move_hint(&mut commands, texture, to);
+        commands.entity(entity).insert(TargetHighlight);
+    }
+}
@@ -0,0 +1,121 @@
+use crate::animation::*;
+use crate::buttons::*;
+use crate::common::*;
+use crate::difficulty::*;
+use crate::game_state::*;
+use crate::game_textures::*;
+use crate::search::*;
+use bevy::prelude::*;
+use debug_print::debug_println;
+use std::thread::{self, JoinHandle};
+
+const ARROW_COLOR: Color = Color::rgba(0.1, 0.6, 0.1, 0.8);
+const ARROW_Z: f32 = 3.;
+
+#[derive(Component)]
+pub struct HintArrow;
+
+// The hint is searched in the background, for the position it was asked in.
+#[derive(Default)]
+pub struct HintSearch {
+    search: Option<(GameState, JoinHandle<Option<Move>>)>,
+}
+
+pub fn spawn_arrow(
+    commands: &mut Commands,
+    game_textures: &GameTextures,
+    from: Position,
+    to: Position,
+) {
+    // Like the other sprites, the arrow is sized for the unscaled board and
+    // scaled by fit_board_system.
+    let scale = unsafe { BOARD_SCALE };
+    let start = real_position(from).truncate() / scale;
+    let end = real_position(to).truncate() / scale;
+    let direction = (end - start).normalize();
+    let rotation = Quat::from_rotation_z(direction.y.atan2(direction.x));
+    let head_length = 0.4 * IMAGE_SIZE.0;
+    let shaft_length = (end - start).length() - head_length;
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: ARROW_COLOR,
+                custom_size: Some(Vec2::new(shaft_length, 0.15 * IMAGE_SIZE.0)),
+                ..Default::default()
+            },
+            transform: Transform {
+                translation: ((start + direction * shaft_length / 2.) * scale).extend(ARROW_Z),
+                rotation,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(HintArrow);
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: ARROW_COLOR,
+                custom_size: Some(Vec2::new(head_length, 0.45 * IMAGE_SIZE.0)),
+                ..Default::default()
+            },
+            texture: game_textures.arrow_head.clone(),
+            transform: Transform {
+                translation: ((end - direction * head_length / 2.) * scale).extend(ARROW_Z),
+                rotation,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(HintArrow);
+}
+
+pub fn hint_system(
+    keys: Res<Input<KeyCode>>,
+    query_buttons: Query<(&Interaction, &ActionButton), Changed<Interaction>>,
+    query_arrows: Query<Entity, With<HintArrow>>,
+    game_textures: Res<GameTextures>,
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    difficulty: Res<Difficulty>,
+    mut hint_search: ResMut<HintSearch>,
+    animation: Res<Animation>,
+) {
+    if game_state.is_changed() {
+        for entity in query_arrows.iter() {
+            commands.entity(entity).despawn();
+        }
+    }
+    let is_finished = match &hint_search.search {
+        Some((_, thread)) => thread.is_finished(),
+        None => false,
+    };
+    if is_finished {
+        // A hint for a position which has been left is dropped.
+        let (hint_state, thread) = hint_search.search.take().unwrap();
+        let hint_move = thread.join().unwrap();
+        debug_println!("hint = {:?}", hint_move);
+        match hint_move {
+            Some((from, to)) if hint_state == *game_state => {
+                spawn_arrow(&mut commands, &game_textures, from, to)
+            }
+            _ => {}
+        }
+    }
+    let hint = (ctrl_pressed(&keys) && keys.just_pressed(KeyCode::H))
+        || is_clicked(&query_buttons, ActionButton::Hint);
+    let is_searching = hint_search.search.is_some();
+    if !hint || !game_state.player_moves || animation.is_running() || is_searching {
+        return;
+    }
+    for entity in query_arrows.iter() {
+        commands.entity(entity).despawn();
+    }
+    // Hints always use the full strength of the chosen depth.
+    let hint_difficulty = Difficulty::from_depth(difficulty.depth);
+    let hint_state = game_state.clone();
+    let thread = thread::spawn(move || {
+        let (lines, _depth) = multi_pv(&hint_state, &hint_difficulty, 1, &mut rand::thread_rng());
+        lines.first().and_then(|line| line.moves.first()).copied()
+    });
+    hint_search.search = Some((game_state.clone(), thread));
+}
@@ -0,0 +1,207 @@
+use crate::animation::*;
+use crate::clock::*;
+use crate::common::*;
+use crate::difficulty::*;
+use crate::game_state::*;
+use crate::game_status::*;
+use crate::game_textures::*;
+use crate::mouse_pressed::*;
+use crate::move_history::*;
+use crate::notation::*;
+use crate::theme::*;
+use bevy::prelude::*;
+
+const CURSOR_COLOR: Color = Color::rgba(0.2, 0.5, 1., 0.9);
+const CURSOR_Z: f32 = 1.7;
+
+// Typed moves are submitted with Enter. The arrow keys move the keyboard
+// cursor and Space acts like a click on the square under it.
+pub struct MoveInput {
+    pub text: String,
+    pub message: String,
+    pub cursor: Option<Position>,
+}
+
+#[derive(Component)]
+pub struct MoveInputText;
+
+#[derive(Component)]
+pub struct KeyboardCursor;
+
+pub fn create_move_input(mut commands: Commands, game_textures: Res<GameTextures>) {
+    commands
+        .spawn_bundle(
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: game_textures.font.clone(),
+                    font_size: 20.,
+                    color: Color::WHITE,
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(10.),
+                    bottom: Val::Px(60.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+        )
+        .insert(MoveInputText);
+}
+
+fn moved_cursor(cursor: Position, keys: &Input<KeyCode>) -> Position {
+    // The arrows follow the screen, which is upside down when flipped.
+    let direction = if unsafe { BOARD_FLIPPED } { -1 } else { 1 };
+    let (mut x, mut y) = (cursor.0, cursor.1);
+    if keys.just_pressed(KeyCode::Left) {
+        x -= direction;
+    }
+    if keys.just_pressed(KeyCode::Right) {
+        x += direction;
+    }
+    if keys.just_pressed(KeyCode::Down) {
+        y -= direction;
+    }
+    if keys.just_pressed(KeyCode::Up) {
+        y += direction;
+    }
+    Position(x.clamp(0, 7), y.clamp(0, 7))
+}
+
+pub fn keyboard_input_system(
+    keys: Res<Input<KeyCode>>,
+    mut received_characters: EventReader<ReceivedCharacter>,
+    mut move_input: ResMut<MoveInput>,
+    game_textures: Res<GameTextures>,
+    mut commands: Commands,
+    query: Query<(Entity, &mut Position, &mut Transform, &mut Handle<Image>)>,
+    query_menus: Query<(), Or<(With<DifficultyMenu>, With<ThemeMenu>)>>,
+    mut selected_square: ResMut<SelectedSquare>,
+    mut game_state: ResMut<GameState>,
+    mut move_history: ResMut<MoveHistory>,
+    mut animation: ResMut<Animation>,
+    clock: Option<Res<Clock>>,
+) {
+    // The menus take the digits and the shortcuts take the letters.
+    let characters = received_characters
+        .iter()
+        .map(|received_character| received_character.char)
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '#' | '=' | '-'))
+        .collect::<String>();
+    if query_menus.is_empty() && !ctrl_pressed(&keys) {
+        move_input.text += &characters;
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        move_input.text.pop();
+    }
+    if keys.just_pressed(KeyCode::Escape) {
+        move_input.text.clear();
+        move_input.message.clear();
+    }
+    let arrows = [KeyCode::Left, KeyCode::Right, KeyCode::Up, KeyCode::Down];
+    if keys.any_just_pressed(arrows) {
+        let cursor = move_input.cursor.unwrap_or(Position(4, 1));
+        move_input.cursor = Some(moved_cursor(cursor, &keys));
+    }
+    let submit = keys.just_pressed(KeyCode::Return) && !move_input.text.is_empty();
+    let click = keys.just_pressed(KeyCode::Space)
+        || (keys.just_pressed(KeyCode::Return) && move_input.text.is_empty());
+    if !submit && !click {
+        return;
+    }
+    if animation.is_running() || !game_state.player_moves {
+        return;
+    }
+    if GameStatus::of(&game_state, clock.as_deref()).is_game_over() {
+        return;
+    }
+    let player_move = if submit {
+        let text = std::mem::take(&mut move_input.text);
+        match parse_move(&game_state, &text) {
+            Some(parsed_move) => Some(parsed_move),
+            None => {
+                move_input.message = format!("Illegal move: {}", text);
+                return;
+            }
+        }
+    } else {
+        let cursor = match move_input.cursor {
+            Some(cursor) => cursor,
+            None => return,
+        };
+        match selected_square.position {
+            Some(from)
+                if from != cursor
+                    && game_state.generate_legal_moves().contains(&(from, cursor)) =>
+            {
+                Some((from, cursor))
+            }
+            Some(from) if from == cursor => {
+                selected_square.position = None;
+                None
+            }
+            _ => {
+                selected_square.position = None;
+                select_square(&game_state, &mut selected_square, cursor);
+                None
+            }
+        }
+    };
+    if let Some((from, to)) = player_move {
+        move_input.message.clear();
+        selected_square.position = None;
+        game_state.player_move(
+            game_textures,
+            &mut commands,
+            query,
+            &mut move_history,
+            clock.as_deref(),
+            &mut animation,
+            from,
+            to,
+        );
+    }
+}
+
+pub fn move_input_display_system(
+    move_input: Res<MoveInput>,
+    game_textures: Res<GameTextures>,
+    mut commands: Commands,
+    mut query_text: Query<&mut Text, With<MoveInputText>>,
+    mut query_cursor: Query<(&mut Transform, &mut BoardSquare), With<KeyboardCursor>>,
+) {
+    if !move_input.is_changed() {
+        return;
+    }
+    for mut text in query_text.iter_mut() {
+        text.sections[0].value = format!("Move: {}_\n{}", move_input.text, move_input.message);
+    }
+    let position = match move_input.cursor {
+        Some(position) => position,
+        None => return,
+    };
+    let translation = real_position(position) + Vec3::new(0., 0., CURSOR_Z);
+    if query_cursor.is_empty() {
+        let mut transform = Transform::from_translation(translation);
+        transform.scale *= SCALING_FACTOR;
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: CURSOR_COLOR,
+                    ..Default::default()
+                },
+                texture: game_textures.cursor_frame.clone(),
+                transform,
+                ..Default::default()
+            })
+            .insert(KeyboardCursor)
+            .insert(BoardSquare { position });
+    }
+    for (mut transform, mut board_square) in query_cursor.iter_mut() {
+        transform.translation = translation;
+        board_square.position = position;
+    }
+}
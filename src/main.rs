@@ -1,23 +1,71 @@
 use bevy::prelude::*;
 use std::env;
 
+mod analysis;
+mod animation;
+mod board_layout;
+mod buttons;
+mod clock;
 mod common;
 mod computer_moves;
 mod create_physical_board;
 mod cursor;
+mod difficulty;
+mod elo;
+mod eval_params;
 mod game_state;
+mod game_status;
 mod game_textures;
+mod highlights;
+mod hint;
+mod keyboard_input;
+mod match_runner;
 mod mouse_pressed;
+mod move_history;
+mod new_game;
+mod notation;
+mod opening_book;
+mod pgn;
 mod physical_board;
 mod piece;
 mod piece_square_tables;
+mod polyglot_random;
 mod program_options;
+mod puzzle;
+mod review;
+mod search;
 mod setup;
+mod side_panel;
 mod spawn_piece;
 mod spawn_tile;
+mod sprt;
+mod tablebase;
+mod theme;
+mod threats;
+mod tui;
+mod uci;
 
 fn main() {
     program_options::program_options(env::args().collect::<Vec<String>>());
+    // The tables are ready before any search, so that the moves of a seeded
+    // game do not depend on how fast they are loaded or generated.
+    tablebase::load_tables();
+    if unsafe { program_options::TUI } {
+        tui::run();
+        return;
+    }
+    if unsafe { program_options::UCI } {
+        uci::run();
+        return;
+    }
+    if let Some(sprt) = unsafe { program_options::SPRT } {
+        sprt::run(sprt, unsafe { program_options::MATCH_GAMES });
+        return;
+    }
+    if let Some(games) = unsafe { program_options::MATCH_GAMES } {
+        match_runner::run(games);
+        return;
+    }
     App::new()
         .add_plugins(DefaultPlugins)
         .add_startup_system(setup::setup)
@@ -29,8 +77,38 @@ fn main() {
             StartupStage::PostStartup,
             create_physical_board::create_pieces,
         )
+        .add_startup_system_to_stage(StartupStage::PostStartup, buttons::create_buttons)
+        .add_startup_system_to_stage(StartupStage::PostStartup, clock::create_clocks)
+        .add_startup_system_to_stage(StartupStage::PostStartup, side_panel::create_side_panel)
+        .add_startup_system_to_stage(StartupStage::PostStartup, keyboard_input::create_move_input)
+        .add_startup_system_to_stage(StartupStage::PostStartup, analysis::create_analysis_panel)
+        .add_startup_system_to_stage(StartupStage::PostStartup, puzzle::create_puzzle_panel)
         .add_system(cursor::cursor_position_system)
         .add_system(mouse_pressed::mouse_pressed_system)
+        .add_system(mouse_pressed::drag_piece_system)
+        .add_system(mouse_pressed::mouse_released_system)
         .add_system(computer_moves::computer_moves_system)
+        .add_system(difficulty::difficulty_menu_system)
+        .add_system(buttons::button_color_system)
+        .add_system(move_history::move_history_system)
+        .add_system(hint::hint_system)
+        .add_system(threats::threats_system)
+        .add_system(animation::animation_system)
+        .add_system(clock::clock_system)
+        .add_system(side_panel::side_panel_system)
+        .add_system(new_game::new_game_system)
+        .add_system(board_layout::fit_board_system)
+        .add_system(board_layout::flip_board_system)
+        .add_system(board_layout::board_orientation_system)
+        .add_system(theme::theme_menu_system)
+        .add_system(highlights::last_move_highlight_system)
+        .add_system(highlights::check_highlight_system)
+        .add_system(highlights::selection_highlight_system)
+        .add_system(keyboard_input::keyboard_input_system)
+        .add_system(keyboard_input::move_input_display_system)
+        .add_system(analysis::analysis_system)
+        .add_system(review::review_system)
+        .add_system(puzzle::puzzle_system)
+        .add_system(puzzle::puzzle_text_system)
         .run();
 }
@@ -0,0 +1,335 @@
+use crate::common::*;
+use crate::computer_moves::*;
+use crate::difficulty::*;
+use crate::elo::*;
+use crate::eval_params::*;
+use crate::game_state::*;
+use crate::game_status::*;
+use crate::notation::*;
+use crate::opening_book::*;
+use crate::pgn::*;
+use crate::piece::*;
+use crate::program_options::*;
+use crate::setup::*;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::io::Write;
+
+// Every pair of games starts with a few random moves, so that the games differ
+// even between deterministic engines.
+const OPENING_PLIES: usize = 4;
+// A game still going on after this many plies is adjudicated as a draw.
+const MAX_PLIES: usize = 400;
+const REPETITIONS: u32 = 3;
+// Fifty moves of both sides without a capture or a pawn move.
+const FIFTY_MOVES_PLIES: usize = 100;
+const MATCH_PGN: &str = "match.pgn";
+
+pub struct EngineConfig {
+    pub name: String,
+    pub difficulty: Difficulty,
+}
+
+impl EngineConfig {
+    // A spec is a comma separated list like "depth=3,eval=params.txt" or
+    // "level=5".
+    pub fn parse(spec: &str) -> Result<EngineConfig, String> {
+        let mut difficulty = Difficulty::from_depth(unsafe { DEPTH });
+        let mut eval_params = EvalParams::default();
+        for setting in spec.split(',').filter(|setting| !setting.is_empty()) {
+            let (key, value) = setting
+                .split_once('=')
+                .ok_or(format!("invalid engine setting {}", setting))?;
+            let invalid_value = || format!("invalid value of {}: {}", key, value);
+            match key {
+                "depth" => {
+                    difficulty = Difficulty::from_depth(value.parse().map_err(|_| invalid_value())?)
+                }
+                "level" => {
+                    difficulty = Difficulty::from_level(value.parse().map_err(|_| invalid_value())?)
+                }
+                "eval" => {
+                    eval_params = EvalParams::load(value)
+                        .map_err(|error| format!("could not load {}: {}", value, error))?
+                }
+                _ => return Err(format!("unknown engine setting {}", key)),
+            }
+        }
+        difficulty.eval_params = eval_params;
+        Ok(EngineConfig {
+            name: spec.to_string(),
+            difficulty,
+        })
+    }
+}
+
+pub struct PlayedGame {
+    pub moves: Vec<(GameState, Move)>,
+    pub status: GameStatus,
+}
+
+impl PlayedGame {
+    // 1 for a win of White, 0.5 for a draw and 0 for a win of Black.
+    pub fn white_score(&self) -> f64 {
+        match self.status.result() {
+            "1-0" => 1.,
+            "0-1" => 0.,
+            _ => 0.5,
+        }
+    }
+}
+
+pub fn random_opening(rng: &mut StdRng) -> Vec<Move> {
+    let mut game_state = start_position();
+    let mut opening = Vec::new();
+    for _ in 0..OPENING_PLIES {
+        let (from, to) = match game_state.generate_safe_moves().choose(rng) {
+            Some(opening_move) => *opening_move,
+            None => break,
+        };
+        game_state.move_piece(from, to, true);
+        opening.push((from, to));
+    }
+    opening
+}
+
+// The plies since the last capture or pawn move.
+fn next_quiet_plies(game_state: &GameState, (from, to): Move, quiet_plies: usize) -> usize {
+    let piece = game_state.board[from.0 as usize][from.1 as usize];
+    let is_capture = game_state.board[to.0 as usize][to.1 as usize].is_some();
+    if is_capture || matches!(piece, Some(piece) if piece.piece_type == PieceType::Pawn) {
+        0
+    } else {
+        quiet_plies + 1
+    }
+}
+
+pub fn play_game(
+    white: &EngineConfig,
+    black: &EngineConfig,
+    opening: &[Move],
+    opening_book: Option<&OpeningBook>,
+    rng: &mut StdRng,
+) -> PlayedGame {
+    let mut game_state = start_position();
+    let mut moves = Vec::new();
+    let mut repetitions = HashMap::new();
+    let mut quiet_plies = 0;
+    let mut engine_info = EngineInfo::Idle;
+    loop {
+        let repetition = repetitions.entry(game_state.clone()).or_insert(0);
+        *repetition += 1;
+        let mut status = GameStatus::of(&game_state, None);
+        if !status.is_game_over() {
+            if *repetition >= REPETITIONS {
+                status = GameStatus::Repetition;
+            } else if quiet_plies >= FIFTY_MOVES_PLIES {
+                status = GameStatus::FiftyMoves;
+            }
+        }
+        if status.is_game_over() || moves.len() >= MAX_PLIES {
+            return PlayedGame { moves, status };
+        }
+        if let Some(&(from, to)) = opening.get(moves.len()) {
+            quiet_plies = next_quiet_plies(&game_state, (from, to), quiet_plies);
+            moves.push((game_state.clone(), (from, to)));
+            game_state.move_piece(from, to, true);
+            continue;
+        }
+        let engine = match game_state.now_moves {
+            PieceColor::White => white,
+            PieceColor::Black => black,
+        };
+        let chosen_move = choose_computer_move(
+            &game_state,
+            opening_book,
+            &engine.difficulty,
+            rng,
+            &mut engine_info,
+        );
+        let (from, to) = match chosen_move {
+            Some(chosen_move) => chosen_move,
+            None => return PlayedGame { moves, status },
+        };
+        quiet_plies = next_quiet_plies(&game_state, (from, to), quiet_plies);
+        moves.push((game_state.clone(), (from, to)));
+        game_state.move_piece(from, to, true);
+    }
+}
+
+pub fn append_pgn(
+    pgn_path: &str,
+    event: &str,
+    round: u32,
+    white: &EngineConfig,
+    black: &EngineConfig,
+    game: &PlayedGame,
+) {
+    let headers = [
+        ("Event", event.to_string()),
+        ("Round", round.to_string()),
+        ("White", white.name.clone()),
+        ("Black", black.name.clone()),
+    ];
+    let text = pgn(&headers, &game.moves, game.status.result());
+    let written = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(pgn_path)
+        .and_then(|mut file| file.write_all(text.as_bytes()));
+    if let Err(error) = written {
+        println!("Could not write {}: {}", pgn_path, error);
+    }
+}
+
+pub fn parse_engines() -> (EngineConfig, EngineConfig) {
+    let parse = |spec: Option<&str>| {
+        let spec = spec.map_or_else(|| format!("depth={}", unsafe { DEPTH }), String::from);
+        EngineConfig::parse(&spec).unwrap_or_else(|error| {
+            println!("Invalid engine {}: {}", spec, error);
+            std::process::exit(1);
+        })
+    };
+    unsafe { (parse(ENGINE1), parse(ENGINE2)) }
+}
+
+pub fn load_openings(path: &str) -> io::Result<Vec<Vec<Move>>> {
+    let mut openings = Vec::new();
+    for line in fs::read_to_string(path)?.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut game_state = start_position();
+        let mut opening = Vec::new();
+        // Move numbers like "1." or "1...e5" are skipped.
+        for token in line.split_whitespace() {
+            let token = token.rsplit('.').next().unwrap_or_default();
+            if token.is_empty() {
+                continue;
+            }
+            let (from, to) = parse_move(&game_state, token).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("illegal move {} in {}", token, line),
+                )
+            })?;
+            game_state.move_piece(from, to, true);
+            opening.push((from, to));
+        }
+        openings.push(opening);
+    }
+    Ok(openings)
+}
+
+// The engines, the openings and the output shared by the match and the SPRT
+// modes.
+pub struct EngineMatch {
+    pub engine1: EngineConfig,
+    pub engine2: EngineConfig,
+    pub score: MatchScore,
+    opening_book: Option<OpeningBook>,
+    openings: Vec<Vec<Move>>,
+    opening: Vec<Move>,
+    pgn_path: &'static str,
+    rng: StdRng,
+}
+
+impl EngineMatch {
+    pub fn new() -> EngineMatch {
+        let (engine1, engine2) = parse_engines();
+        let openings = match unsafe { OPENINGS_PATH } {
+            Some(path) => load_openings(path).unwrap_or_else(|error| {
+                println!("Could not load the openings {}: {}", path, error);
+                std::process::exit(1);
+            }),
+            None => Vec::new(),
+        };
+        let (rng, _) = initial_rng_and_color();
+        let pgn_path = unsafe { PGN_PATH }.unwrap_or(MATCH_PGN);
+        if let Err(error) = File::create(pgn_path) {
+            println!("Could not create {}: {}", pgn_path, error);
+        }
+        EngineMatch {
+            engine1,
+            engine2,
+            score: MatchScore::default(),
+            opening_book: load_opening_book(),
+            openings,
+            opening: Vec::new(),
+            pgn_path,
+            rng,
+        }
+    }
+    // Every opening is played twice, the first engine has White in the odd
+    // rounds and Black in the even ones.
+    pub fn play_round(&mut self, round: u32, event: &str) {
+        if round % 2 == 1 {
+            let pair = (round / 2) as usize;
+            self.opening = if self.openings.is_empty() {
+                random_opening(&mut self.rng)
+            } else {
+                self.openings[pair % self.openings.len()].clone()
+            };
+        }
+        let (white, black) = if round % 2 == 1 {
+            (&self.engine1, &self.engine2)
+        } else {
+            (&self.engine2, &self.engine1)
+        };
+        let game = play_game(
+            white,
+            black,
+            &self.opening,
+            self.opening_book.as_ref(),
+            &mut self.rng,
+        );
+        let engine1_score = if round % 2 == 1 {
+            game.white_score()
+        } else {
+            1. - game.white_score()
+        };
+        self.score.add(engine1_score);
+        append_pgn(self.pgn_path, event, round, white, black, &game);
+        let reason = if game.status.is_game_over() {
+            game.status.description()
+        } else {
+            String::from("move limit")
+        };
+        println!(
+            "Game {}: {} - {} {} ({}), {} plies",
+            round,
+            white.name,
+            black.name,
+            game.status.result(),
+            reason,
+            game.moves.len()
+        );
+    }
+    pub fn print_score(&self) {
+        let (elo, margin) = self.score.elo();
+        println!(
+            "{} vs {}: +{} ={} -{} ({:.1}%), Elo difference {:+.1} +/- {:.1}",
+            self.engine1.name,
+            self.engine2.name,
+            self.score.wins,
+            self.score.draws,
+            self.score.losses,
+            100. * self.score.score(),
+            elo,
+            margin
+        );
+    }
+}
+
+// Plays the games headless.
+pub fn run(games: u32) {
+    let mut engine_match = EngineMatch::new();
+    for round in 1..=games {
+        engine_match.play_round(round, "Engine match");
+    }
+    engine_match.print_score();
+}
@@ -1,47 +1,152 @@
+use crate::animation::*;
+use crate::clock::*;
 use crate::common::*;
 use crate::game_state::*;
+use crate::game_status::*;
 use crate::game_textures::*;
-use crate::spawn_tile::*;
+use crate::move_history::*;
 use bevy::prelude::*;
 
+const DRAGGED_PIECE_Z: f32 = 2.;
+
+fn is_selectable(game_state: &GameState, position: Position) -> bool {
+    match game_state.board[position.0 as usize][position.1 as usize] {
+        Some(piece) => game_state.player_moves && piece.piece_color == game_state.now_moves,
+        None => false,
+    }
+}
+
+// The selection highlights follow the selected square on their own.
+pub fn select_square(
+    game_state: &GameState,
+    selected_square: &mut SelectedSquare,
+    position: Position,
+) {
+    if is_selectable(game_state, position) {
+        selected_square.position = Some(position);
+    }
+}
+
+fn start_dragging(
+    query: &Query<(Entity, &mut Position, &mut Transform, &mut Handle<Image>)>,
+    selected_square: &SelectedSquare,
+    dragged_piece: &mut DraggedPiece,
+) {
+    if let Some(selected_square_position) = selected_square.position {
+        dragged_piece.entity = query
+            .iter()
+            .find(|(_entity, position, _transform, _texture)| {
+                **position == selected_square_position
+            })
+            .map(|(entity, _position, _transform, _texture)| entity);
+    }
+}
+
 pub fn mouse_pressed_system(
     buttons: Res<Input<MouseButton>>,
     mouse_position: Res<MousePosition>,
     mut selected_square: ResMut<SelectedSquare>,
+    mut dragged_piece: ResMut<DraggedPiece>,
     query: Query<(Entity, &mut Position, &mut Transform, &mut Handle<Image>)>,
-    query_highlight: Query<Entity, With<Highlight>>,
     game_textures: Res<GameTextures>,
     mut commands: Commands,
     mut game_state: ResMut<GameState>,
+    mut move_history: ResMut<MoveHistory>,
+    mut animation: ResMut<Animation>,
+    clock: Option<Res<Clock>>,
 ) {
+    if animation.is_running() {
+        return;
+    }
     if buttons.just_pressed(MouseButton::Left) {
+        if GameStatus::of(&game_state, clock.as_deref()).is_game_over() {
+            return;
+        }
         if let Some(position) = mouse_position.position {
             if let Some(selected_square_position) = selected_square.position {
-                delete_highlight(&mut commands, &query_highlight);
                 selected_square.position = None;
-                game_state.player_move(
-                    game_textures,
-                    &mut commands,
-                    query,
-                    selected_square_position,
-                    position,
-                );
-            } else {
-                selected_square.position = mouse_position.position;
-                spawn_tile(
-                    &mut commands,
-                    game_textures.highlight.clone(),
-                    position,
-                    true,
-                );
+                if game_state
+                    .generate_legal_moves()
+                    .contains(&(selected_square_position, position))
+                {
+                    game_state.player_move(
+                        game_textures,
+                        &mut commands,
+                        query,
+                        &mut move_history,
+                        clock.as_deref(),
+                        &mut animation,
+                        selected_square_position,
+                        position,
+                    );
+                    return;
+                }
             }
+            select_square(&game_state, &mut selected_square, position);
+            start_dragging(&query, &selected_square, &mut dragged_piece);
         } else {
             selected_square.position = None;
-            delete_highlight(&mut commands, &query_highlight);
         }
     }
     if buttons.just_pressed(MouseButton::Right) {
         selected_square.position = None;
-        delete_highlight(&mut commands, &query_highlight);
+    }
+}
+
+pub fn drag_piece_system(
+    mouse_position: Res<MousePosition>,
+    dragged_piece: Res<DraggedPiece>,
+    mut query: Query<&mut Transform, With<Position>>,
+) {
+    if let (Some(entity), Some(world_position)) =
+        (dragged_piece.entity, mouse_position.world_position)
+    {
+        if let Ok(mut transform) = query.get_mut(entity) {
+            transform.translation = world_position.extend(DRAGGED_PIECE_Z);
+        }
+    }
+}
+
+// Dropping the piece on its own square keeps it selected, so a move can still
+// be made with a second click.
+pub fn mouse_released_system(
+    buttons: Res<Input<MouseButton>>,
+    mouse_position: Res<MousePosition>,
+    mut selected_square: ResMut<SelectedSquare>,
+    mut dragged_piece: ResMut<DraggedPiece>,
+    mut query: Query<(Entity, &mut Position, &mut Transform, &mut Handle<Image>)>,
+    game_textures: Res<GameTextures>,
+    mut commands: Commands,
+    mut game_state: ResMut<GameState>,
+    mut move_history: ResMut<MoveHistory>,
+    mut animation: ResMut<Animation>,
+    clock: Option<Res<Clock>>,
+) {
+    if !buttons.just_released(MouseButton::Left) {
+        return;
+    }
+    let entity = match dragged_piece.entity.take() {
+        Some(entity) => entity,
+        None => return,
+    };
+    // A dropped piece slides from the cursor to its square.
+    if let (Some(from), Some(to)) = (selected_square.position, mouse_position.position) {
+        if from != to && game_state.generate_legal_moves().contains(&(from, to)) {
+            selected_square.position = None;
+            game_state.player_move(
+                game_textures,
+                &mut commands,
+                query,
+                &mut move_history,
+                clock.as_deref(),
+                &mut animation,
+                from,
+                to,
+            );
+            return;
+        }
+    }
+    if let Ok((_entity, position, mut transform, _texture)) = query.get_mut(entity) {
+        transform.translation = real_piece_position(*position);
     }
 }
@@ -0,0 +1,123 @@
+use crate::animation::*;
+use crate::buttons::*;
+use crate::clock::*;
+use crate::common::*;
+use crate::game_state::*;
+use crate::game_textures::*;
+use crate::physical_board::*;
+use bevy::prelude::*;
+use debug_print::debug_println;
+use std::time::Duration;
+
+// Every entry holds the game state from before the move. Undoing stops at a
+// position where the player moves, so in the one player mode both the reply of
+// the engine and the move of the player are taken back. With clocks, the
+// times of both sides are kept from before every move and from after every
+// undone move.
+#[derive(Default)]
+pub struct MoveHistory {
+    pub moves: Vec<(GameState, Move)>,
+    pub undone_moves: Vec<Move>,
+    clock_times: Vec<Option<(Duration, Duration)>>,
+    undone_clock_times: Vec<Option<(Duration, Duration)>>,
+}
+
+impl MoveHistory {
+    pub fn record(&mut self, game_state: &GameState, recorded_move: Move, clock: Option<&Clock>) {
+        self.moves.push((game_state.clone(), recorded_move));
+        self.clock_times.push(clock.map(Clock::times));
+        self.clear_undone();
+    }
+    pub fn clear(&mut self) {
+        self.moves.clear();
+        self.clock_times.clear();
+        self.clear_undone();
+    }
+    pub fn clear_undone(&mut self) {
+        self.undone_moves.clear();
+        self.undone_clock_times.clear();
+    }
+    pub fn undo(&mut self, game_state: &mut GameState, clock: Option<&mut Clock>) -> bool {
+        let index = match self.moves.iter().rposition(|(state, _)| state.player_moves) {
+            Some(index) => index,
+            None => return false,
+        };
+        let mut times = clock.as_deref().map(Clock::times);
+        while self.moves.len() > index {
+            let (state, undone_move) = self.moves.pop().unwrap();
+            self.undone_moves.push(undone_move);
+            self.undone_clock_times.push(times);
+            times = self.clock_times.pop().flatten();
+            *game_state = state;
+        }
+        if let (Some(clock), Some(times)) = (clock, times) {
+            clock.restore(times, game_state.now_moves, index > 0);
+        }
+        true
+    }
+    pub fn redo(&mut self, game_state: &mut GameState, clock: Option<&mut Clock>) -> bool {
+        let mut redone = false;
+        let mut times = clock.as_deref().map(Clock::times);
+        while let Some((from, to)) = self.undone_moves.pop() {
+            self.moves.push((game_state.clone(), (from, to)));
+            self.clock_times.push(times);
+            times = self.undone_clock_times.pop().flatten();
+            game_state.play_move(from, to);
+            redone = true;
+            if game_state.player_moves {
+                break;
+            }
+        }
+        if let (Some(clock), Some(times)) = (clock, times) {
+            if redone {
+                clock.restore(times, game_state.now_moves, true);
+            }
+        }
+        redone
+    }
+}
+
+pub fn move_history_system(
+    keys: Res<Input<KeyCode>>,
+    query_buttons: Query<(&Interaction, &ActionButton), Changed<Interaction>>,
+    query_pieces: Query<Entity, With<Position>>,
+    game_textures: Res<GameTextures>,
+    mut commands: Commands,
+    mut selected_square: ResMut<SelectedSquare>,
+    mut dragged_piece: ResMut<DraggedPiece>,
+    mut game_state: ResMut<GameState>,
+    mut move_history: ResMut<MoveHistory>,
+    mut clock: Option<ResMut<Clock>>,
+    animation: Res<Animation>,
+) {
+    if animation.is_running() {
+        return;
+    }
+    let shift_pressed = keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift);
+    let undo = (ctrl_pressed(&keys) && !shift_pressed && keys.just_pressed(KeyCode::Z))
+        || is_clicked(&query_buttons, ActionButton::Undo);
+    let redo = (ctrl_pressed(&keys) && keys.just_pressed(KeyCode::Y))
+        || (ctrl_pressed(&keys) && shift_pressed && keys.just_pressed(KeyCode::Z))
+        || is_clicked(&query_buttons, ActionButton::Redo);
+    let changed = if undo {
+        move_history.undo(&mut game_state, clock.as_deref_mut())
+    } else if redo {
+        move_history.redo(&mut game_state, clock.as_deref_mut())
+    } else {
+        false
+    };
+    if !changed {
+        return;
+    }
+    selected_square.position = None;
+    dragged_piece.entity = None;
+    respawn_pieces(
+        &game_textures,
+        &mut commands,
+        &query_pieces,
+        &game_state.board,
+    );
+    if game_state.player_moves {
+        debug_println!("Your move");
+    }
+}
@@ -0,0 +1,71 @@
+use crate::animation::*;
+use crate::buttons::*;
+use crate::clock::*;
+use crate::common::*;
+use crate::computer_moves::*;
+use crate::create_physical_board::*;
+use crate::game_state::*;
+use crate::game_textures::*;
+use crate::move_history::*;
+use crate::piece::*;
+use crate::program_options::*;
+use crate::puzzle::*;
+use crate::setup::*;
+use bevy::prelude::*;
+
+// The highlights, hint arrows and threats follow the game state on their own.
+// Ctrl+N starts a new game with the same colors, Ctrl+Shift+N swaps them.
+pub fn new_game_system(
+    keys: Res<Input<KeyCode>>,
+    query_buttons: Query<(&Interaction, &ActionButton), Changed<Interaction>>,
+    query_entities: Query<Entity, Or<(With<Position>, With<FadeOut>)>>,
+    game_textures: Res<GameTextures>,
+    mut commands: Commands,
+    mut game_state: ResMut<GameState>,
+    mut selected_square: ResMut<SelectedSquare>,
+    mut dragged_piece: ResMut<DraggedPiece>,
+    mut move_history: ResMut<MoveHistory>,
+    mut engine_info: ResMut<EngineInfo>,
+    mut animation: ResMut<Animation>,
+    clock: Option<ResMut<Clock>>,
+    puzzle_trainer: Option<Res<PuzzleTrainer>>,
+) {
+    // The puzzle trainer goes to the next puzzle instead.
+    if puzzle_trainer.is_some() {
+        return;
+    }
+    let shift_pressed = keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift);
+    let new_game = (ctrl_pressed(&keys) && keys.just_pressed(KeyCode::N))
+        || is_clicked(&query_buttons, ActionButton::NewGame);
+    if !new_game {
+        return;
+    }
+    // Without exactly one human player, the board orientation decides.
+    let mut human_color = if unsafe { NUMBER_OF_PLAYERS } == 1 {
+        if game_state.player_moves {
+            game_state.now_moves
+        } else {
+            game_state.now_moves.opposite()
+        }
+    } else if unsafe { BOARD_FLIPPED } {
+        PieceColor::Black
+    } else {
+        PieceColor::White
+    };
+    if shift_pressed {
+        human_color = human_color.opposite();
+    }
+    for entity in query_entities.iter() {
+        commands.entity(entity).despawn();
+    }
+    *game_state = new_game_state(human_color);
+    spawn_pieces(&mut commands, &game_textures, &mut game_state);
+    selected_square.position = None;
+    dragged_piece.entity = None;
+    move_history.clear();
+    *engine_info = EngineInfo::Idle;
+    animation.time_left = 0.;
+    if let Some(mut clock) = clock {
+        *clock = Clock::new(clock.time_control, PieceColor::White);
+    }
+}
@@ -0,0 +1,357 @@
+use crate::common::*;
+use crate::game_state::*;
+use crate::game_status::*;
+use crate::piece::*;
+
+pub fn square_name(position: Position) -> String {
+    format!(
+        "{}{}",
+        (b'a' + position.0 as u8) as char,
+        (b'1' + position.1 as u8) as char
+    )
+}
+
+fn piece_letter(piece_type: PieceType) -> &'static str {
+    match piece_type {
+        PieceType::King => "K",
+        PieceType::Queen => "Q",
+        PieceType::Rook => "R",
+        PieceType::Bishop => "B",
+        PieceType::Knight => "N",
+        PieceType::Pawn => "",
+    }
+}
+
+// The castling rights of a side, queen side first.
+fn castling_rights(board: &Board, piece_color: PieceColor) -> (bool, bool) {
+    let y = match piece_color {
+        PieceColor::White => 0,
+        PieceColor::Black => 7,
+    };
+    let is_piece = |x: usize, piece_type: PieceType| matches!(board[x][y], Some(piece) if piece.piece_type == piece_type && piece.piece_color == piece_color);
+    let is_king_home = is_piece(4, PieceType::King);
+    (
+        is_king_home && is_piece(0, PieceType::Rook),
+        is_king_home && is_piece(7, PieceType::Rook),
+    )
+}
+
+// The piece placement, the side to move and the castling rights are read. The
+// engine has no en passant and castles whenever the king and the rook stand on
+// their squares, so a position with other castling rights is rejected.
+pub fn parse_fen(fen: &str) -> Result<GameState, String> {
+    let mut fields = fen.split_whitespace();
+    let ranks = fields
+        .next()
+        .ok_or("missing piece placement")?
+        .split('/')
+        .collect::<Vec<&str>>();
+    let now_moves = match fields.next().unwrap_or("w") {
+        "w" => PieceColor::White,
+        "b" => PieceColor::Black,
+        side => return Err(format!("invalid side to move {}", side)),
+    };
+    if ranks.len() != 8 {
+        return Err(format!("{} ranks instead of 8", ranks.len()));
+    }
+    let mut board: Board = [[None; 8]; 8];
+    for (row, rank) in ranks.iter().enumerate() {
+        let y = 7 - row as i8;
+        let mut x = 0;
+        for c in rank.chars() {
+            if let Some(empty_squares) = c.to_digit(10) {
+                x += empty_squares as i8;
+                continue;
+            }
+            let piece_type = match c.to_ascii_lowercase() {
+                'k' => PieceType::King,
+                'q' => PieceType::Queen,
+                'r' => PieceType::Rook,
+                'b' => PieceType::Bishop,
+                'n' => PieceType::Knight,
+                'p' => PieceType::Pawn,
+                _ => return Err(format!("invalid piece {}", c)),
+            };
+            let piece_color = if c.is_ascii_uppercase() {
+                PieceColor::White
+            } else {
+                PieceColor::Black
+            };
+            if x >= 8 {
+                return Err(format!("rank {} is too long", rank));
+            }
+            board[x as usize][y as usize] = Some(Piece {
+                piece_color,
+                piece_type,
+                x,
+                y,
+            });
+            x += 1;
+        }
+        if x != 8 {
+            return Err(format!("rank {} does not have 8 squares", rank));
+        }
+    }
+    if let Some(castling) = fields.next() {
+        if castling != "-" && !castling.chars().all(|c| "KQkq".contains(c)) {
+            return Err(format!("invalid castling rights {}", castling));
+        }
+        let white = castling_rights(&board, PieceColor::White);
+        let black = castling_rights(&board, PieceColor::Black);
+        let rights = [
+            (black.1, 'k'),
+            (black.0, 'q'),
+            (white.1, 'K'),
+            (white.0, 'Q'),
+        ];
+        if rights
+            .iter()
+            .any(|(is_allowed, c)| *is_allowed != castling.contains(*c))
+        {
+            return Err(format!(
+                "castling rights {} differ from the kings and rooks on their squares",
+                castling
+            ));
+        }
+    }
+    Ok(GameState {
+        board,
+        now_moves,
+        player_moves: false,
+    })
+}
+
+// Coordinate notation like "g1f3" or "e7e8q" used by UCI.
+pub fn coordinate_notation(game_state: &GameState, (from, to): Move) -> String {
+    let piece = game_state.board[from.0 as usize][from.1 as usize].unwrap();
+    let mut text = square_name(from) + &square_name(to);
+    if piece.piece_type == PieceType::Pawn && (to.1 == 0 || to.1 == 7) {
+        text.push('q');
+    }
+    text
+}
+
+// Standard algebraic notation of a move made in the given position.
+pub fn san(game_state: &GameState, (from, to): Move) -> String {
+    let piece = game_state.board[from.0 as usize][from.1 as usize].unwrap();
+    let is_capture = game_state.board[to.0 as usize][to.1 as usize].is_some();
+    let mut san = String::new();
+    if piece.piece_type == PieceType::King && (to.0 - from.0).abs() == 2 {
+        san += if to.0 > from.0 { "O-O" } else { "O-O-O" };
+    } else if piece.piece_type == PieceType::Pawn {
+        if is_capture {
+            san.push((b'a' + from.0 as u8) as char);
+            san.push('x');
+        }
+        san += &square_name(to);
+        if to.1 == 0 || to.1 == 7 {
+            san += "=Q";
+        }
+    } else {
+        san += piece_letter(piece.piece_type);
+        let others = game_state
+            .generate_safe_moves()
+            .into_iter()
+            .filter(|(other_from, other_to)| {
+                *other_to == to
+                    && *other_from != from
+                    && game_state.board[other_from.0 as usize][other_from.1 as usize]
+                        .map(|other| other.piece_type)
+                        == Some(piece.piece_type)
+            })
+            .map(|(other_from, _)| other_from)
+            .collect::<Vec<Position>>();
+        if !others.is_empty() {
+            let square = square_name(from);
+            if others.iter().all(|other| other.0 != from.0) {
+                san += &square[..1];
+            } else if others.iter().all(|other| other.1 != from.1) {
+                san += &square[1..];
+            } else {
+                san += &square;
+            }
+        }
+        if is_capture {
+            san.push('x');
+        }
+        san += &square_name(to);
+    }
+    let mut next_state = game_state.clone();
+    next_state.move_piece(from, to, true);
+    match GameStatus::of(&next_state, None) {
+        GameStatus::Checkmate(_) => san.push('#'),
+        GameStatus::Check => san.push('+'),
+        _ => {}
+    }
+    san
+}
+
+// Captures, checks and promotions may be marked or not.
+fn normalize(text: &str) -> String {
+    text.replace('0', "O")
+        .chars()
+        .filter(|c| !matches!(c, 'x' | '+' | '#' | '=' | '!' | '?'))
+        .collect()
+}
+
+// Coordinates like "g1f3" or "e7e8q". Pawns only promote to a queen, so an
+// under-promotion like "e7e8n" is not accepted, while "e7e8" is a queen
+// promotion.
+pub fn parse_coordinates(game_state: &GameState, text: &str) -> Option<Move> {
+    let coordinates = text.trim().as_bytes();
+    if !is_coordinates(coordinates) {
+        return None;
+    }
+    let from = Position((coordinates[0] - b'a') as i8, (coordinates[1] - b'1') as i8);
+    let to = Position((coordinates[2] - b'a') as i8, (coordinates[3] - b'1') as i8);
+    let piece = game_state.board[from.0 as usize][from.1 as usize]?;
+    let is_promotion = piece.piece_type == PieceType::Pawn && (to.1 == 0 || to.1 == 7);
+    match coordinates.get(4) {
+        None => {}
+        Some(b'q') if is_promotion => {}
+        Some(_) => return None,
+    }
+    if game_state.generate_legal_moves().contains(&(from, to)) {
+        Some((from, to))
+    } else {
+        None
+    }
+}
+
+fn is_coordinates(text: &[u8]) -> bool {
+    (text.len() == 4 || text.len() == 5)
+        && (b'a'..=b'h').contains(&text[0])
+        && (b'1'..=b'8').contains(&text[1])
+        && (b'a'..=b'h').contains(&text[2])
+        && (b'1'..=b'8').contains(&text[3])
+}
+
+// Accepts standard algebraic notation like "Nf3" and coordinates like "g1f3"
+// or "e7e8q". A promotion may leave out the piece, like "e8".
+pub fn parse_move(game_state: &GameState, text: &str) -> Option<Move> {
+    let text = text.trim();
+    if is_coordinates(text.as_bytes()) {
+        return parse_coordinates(game_state, text);
+    }
+    let text = normalize(text);
+    game_state
+        .generate_legal_moves()
+        .into_iter()
+        .find(|legal_move| {
+            let san = san(game_state, *legal_move);
+            normalize(&san) == text || normalize(&san.replace("=Q", "")) == text
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    fn position(text: &str) -> Position {
+        let square = text.as_bytes();
+        Position((square[0] - b'a') as i8, (square[1] - b'1') as i8)
+    }
+
+    fn play(fen: &str, moves: &[&str]) -> GameState {
+        let mut game_state = parse_fen(fen).unwrap();
+        for text in moves {
+            let (from, to) = parse_move(&game_state, text).unwrap();
+            game_state.move_piece(from, to, true);
+        }
+        game_state
+    }
+
+    #[test]
+    fn parse_coordinates_and_san() {
+        let game_state = play(START_FEN, &[]);
+        let e4 = (position("e2"), position("e4"));
+        assert_eq!(parse_move(&game_state, "e2e4"), Some(e4));
+        assert_eq!(parse_move(&game_state, "e4"), Some(e4));
+        assert_eq!(
+            parse_move(&game_state, "Nf3"),
+            Some((position("g1"), position("f3")))
+        );
+        assert_eq!(parse_move(&game_state, "e2e5"), None);
+        assert_eq!(parse_move(&game_state, "e2e4q"), None);
+        assert_eq!(parse_move(&game_state, "Ke2"), None);
+    }
+
+    #[test]
+    fn parse_castling() {
+        let game_state = play(START_FEN, &["e4", "e5", "Nf3", "Nc6", "Bc4", "Bc5"]);
+        let castling = Some((position("e1"), position("g1")));
+        assert_eq!(parse_move(&game_state, "O-O"), castling);
+        assert_eq!(parse_move(&game_state, "0-0"), castling);
+        assert_eq!(parse_move(&game_state, "e1g1"), castling);
+    }
+
+    #[test]
+    fn parse_promotion() {
+        let game_state = parse_fen("7k/4P3/8/8/8/8/8/K7 w - - 0 1").unwrap();
+        let promotion = Some((position("e7"), position("e8")));
+        for text in ["e7e8q", "e7e8", "e8=Q", "e8Q", "e8", "e8=Q+"] {
+            assert_eq!(parse_move(&game_state, text), promotion, "{}", text);
+        }
+        for text in ["e7e8n", "e7e8x", "e8=N"] {
+            assert_eq!(parse_move(&game_state, text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn parse_fen_positions() {
+        let game_state = parse_fen(START_FEN).unwrap();
+        assert_eq!(game_state.now_moves, PieceColor::White);
+        let white_king = game_state.board[4][0].unwrap();
+        assert_eq!(white_king.piece_type, PieceType::King);
+        assert_eq!(white_king.piece_color, PieceColor::White);
+        assert_eq!(game_state.board[3][7].unwrap().piece_type, PieceType::Queen);
+        assert!(game_state.board[4][3].is_none());
+        let game_state =
+            parse_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+        assert_eq!(game_state.now_moves, PieceColor::Black);
+        assert_eq!(game_state.board[4][3].unwrap().piece_type, PieceType::Pawn);
+        assert!(parse_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").is_ok());
+        assert!(parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR").is_ok());
+    }
+
+    #[test]
+    fn parse_fen_errors() {
+        assert!(parse_fen("").is_err());
+        assert!(parse_fen("8/8/8 w - - 0 1").is_err());
+        assert!(parse_fen("4k3/8/8/8/8/8/8/4K4 w - - 0 1").is_err());
+        assert!(parse_fen("4k3/8/8/8/8/8/8/4X3 w - - 0 1").is_err());
+        assert!(parse_fen("4k3/8/8/8/8/8/8/4K3 x - - 0 1").is_err());
+        // The engine would castle without the rights.
+        assert!(parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1").is_err());
+        assert!(parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQ - 0 1").is_err());
+        // The rights of a king not on its square.
+        assert!(parse_fen("4k3/8/8/8/8/8/8/R4K1R w KQ - 0 1").is_err());
+        assert!(parse_fen("4k3/8/8/8/8/8/8/R4K1R w - - 0 1").is_ok());
+    }
+
+    #[test]
+    fn san_of_moves() {
+        let game_state = play(START_FEN, &["e4", "e5", "Bc4", "Nc6", "Qh5", "Nf6"]);
+        let mate = (position("h5"), position("f7"));
+        assert_eq!(san(&game_state, mate), "Qxf7#");
+        assert_eq!(parse_move(&game_state, "Qxf7"), Some(mate));
+        let game_state = play(START_FEN, &["e4", "e5", "Nf3", "Nc6", "Bc4", "Bc5"]);
+        assert_eq!(san(&game_state, (position("e1"), position("g1"))), "O-O");
+        let game_state = parse_fen("7k/4P3/8/8/8/8/8/K7 w - - 0 1").unwrap();
+        assert_eq!(san(&game_state, (position("e7"), position("e8"))), "e8=Q+");
+    }
+
+    #[test]
+    fn san_disambiguation() {
+        let game_state = parse_fen("4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1").unwrap();
+        assert_eq!(san(&game_state, (position("b1"), position("d2"))), "Nbd2");
+        let game_state = parse_fen("4k3/8/8/R7/8/8/8/R3K3 w Q - 0 1").unwrap();
+        assert_eq!(san(&game_state, (position("a1"), position("a3"))), "R1a3");
+        assert_eq!(
+            parse_move(&game_state, "R5a3"),
+            Some((position("a5"), position("a3")))
+        );
+    }
+}
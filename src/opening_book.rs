@@ -0,0 +1,182 @@
+use crate::common::*;
+use crate::game_state::*;
+use crate::piece::*;
+use crate::polyglot_random::*;
+use rand::Rng;
+use std::fs;
+use std::io;
+
+const ENTRY_SIZE: usize = 16;
+const CASTLE_OFFSET: usize = 768;
+const TURN_OFFSET: usize = 780;
+
+struct BookEntry {
+    key: u64,
+    book_move: u16,
+    weight: u16,
+}
+
+pub struct OpeningBook {
+    entries: Vec<BookEntry>,
+}
+
+fn piece_kind(piece: &Piece) -> usize {
+    let kind = match piece.piece_type {
+        PieceType::Pawn => 0,
+        PieceType::Knight => 1,
+        PieceType::Bishop => 2,
+        PieceType::Rook => 3,
+        PieceType::Queen => 4,
+        PieceType::King => 5,
+    };
+    match piece.piece_color {
+        PieceColor::White => 2 * kind + 1,
+        PieceColor::Black => 2 * kind,
+    }
+}
+
+fn has_piece(
+    game_state: &GameState,
+    position: Position,
+    piece_type: PieceType,
+    piece_color: PieceColor,
+) -> bool {
+    match game_state.board[position.0 as usize][position.1 as usize] {
+        Some(piece) => piece.piece_type == piece_type && piece.piece_color == piece_color,
+        None => false,
+    }
+}
+
+// The engine allows castling whenever the king and the rook stand on their
+// initial squares, so the castling rights are derived from the board. En
+// passant is not supported by the engine, so its keys are never used.
+pub fn polyglot_key(game_state: &GameState) -> u64 {
+    let mut key = 0;
+    for i in 0..8 {
+        for j in 0..8 {
+            if let Some(piece) = game_state.board[i][j] {
+                key ^= POLYGLOT_RANDOM[64 * piece_kind(&piece) + 8 * j + i];
+            }
+        }
+    }
+    let castles = [
+        (PieceColor::White, 0, 7),
+        (PieceColor::White, 0, 0),
+        (PieceColor::Black, 7, 7),
+        (PieceColor::Black, 7, 0),
+    ];
+    for (index, (piece_color, row, rook_file)) in castles.into_iter().enumerate() {
+        if has_piece(game_state, Position(4, row), PieceType::King, piece_color)
+            && has_piece(
+                game_state,
+                Position(rook_file, row),
+                PieceType::Rook,
+                piece_color,
+            )
+        {
+            key ^= POLYGLOT_RANDOM[CASTLE_OFFSET + index];
+        }
+    }
+    if game_state.now_moves == PieceColor::White {
+        key ^= POLYGLOT_RANDOM[TURN_OFFSET];
+    }
+    key
+}
+
+fn decode_move(game_state: &GameState, book_move: u16) -> Move {
+    let to = Position((book_move & 7) as i8, ((book_move >> 3) & 7) as i8);
+    let from = Position(((book_move >> 6) & 7) as i8, ((book_move >> 9) & 7) as i8);
+    let is_king = match game_state.board[from.0 as usize][from.1 as usize] {
+        Some(piece) => piece.piece_type == PieceType::King,
+        None => false,
+    };
+    // Polyglot encodes castling as the king capturing its own rook.
+    if is_king && from.0 == 4 && from.1 == to.1 {
+        if to.0 == 7 {
+            return (from, Position(6, to.1));
+        }
+        if to.0 == 0 {
+            return (from, Position(2, to.1));
+        }
+    }
+    (from, to)
+}
+
+impl OpeningBook {
+    pub fn load(path: &str) -> io::Result<OpeningBook> {
+        let bytes = fs::read(path)?;
+        let entries = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(|chunk| BookEntry {
+                key: u64::from_be_bytes(chunk[0..8].try_into().unwrap()),
+                book_move: u16::from_be_bytes(chunk[8..10].try_into().unwrap()),
+                weight: u16::from_be_bytes(chunk[10..12].try_into().unwrap()),
+            })
+            .collect();
+        Ok(OpeningBook { entries })
+    }
+    fn entries(&self, key: u64) -> &[BookEntry] {
+        let begin = self.entries.partition_point(|entry| entry.key < key);
+        let end = self.entries.partition_point(|entry| entry.key <= key);
+        &self.entries[begin..end]
+    }
+    pub fn choose_move<R: Rng>(&self, game_state: &GameState, rng: &mut R) -> Option<Move> {
+        let legal_moves = game_state.generate_legal_moves();
+        let candidates = self
+            .entries(polyglot_key(game_state))
+            .iter()
+            .map(|entry| {
+                (
+                    decode_move(game_state, entry.book_move),
+                    entry.weight as u32,
+                )
+            })
+            .filter(|(book_move, weight)| *weight > 0 && legal_moves.contains(book_move))
+            .collect::<Vec<(Move, u32)>>();
+        let total_weight: u32 = candidates.iter().map(|(_, weight)| weight).sum();
+        if total_weight == 0 {
+            return None;
+        }
+        let mut choice = rng.gen_range(0..total_weight);
+        for (book_move, weight) in candidates {
+            if choice < weight {
+                return Some(book_move);
+            }
+            choice -= weight;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::*;
+
+    fn key_after(moves: &[&str]) -> u64 {
+        let mut game_state = start_position();
+        for text in moves {
+            let (from, to) = parse_move(&game_state, text).unwrap();
+            game_state.move_piece(from, to, true);
+        }
+        polyglot_key(&game_state)
+    }
+
+    // The keys of the Polyglot book format specification, without the
+    // positions in which en passant is possible.
+    #[test]
+    fn polyglot_keys() {
+        assert_eq!(key_after(&[]), 0x463b96181691fc9c);
+        assert_eq!(key_after(&["e4"]), 0x823c9b50fd114196);
+        assert_eq!(key_after(&["e4", "d5"]), 0x0756b94461c50fb0);
+        assert_eq!(key_after(&["e4", "d5", "e5"]), 0x662fafb965db29d4);
+        assert_eq!(
+            key_after(&["e4", "d5", "e5", "f5", "Ke2"]),
+            0x652a607ca3f242c1
+        );
+        assert_eq!(
+            key_after(&["e4", "d5", "e5", "f5", "Ke2", "Kf7"]),
+            0x00fdd303c946bdd9
+        );
+    }
+}
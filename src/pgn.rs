@@ -0,0 +1,102 @@
+use crate::common::*;
+use crate::game_state::*;
+use crate::notation::*;
+use crate::piece::*;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const LINE_LENGTH: usize = 80;
+// The tags every game starts with, in this order.
+const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+// Today in the format "2022.08.01", computed from the days since 1970 in the
+// proleptic Gregorian calendar.
+fn pgn_date() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let days = (seconds / 86400) as i64 + 719468;
+    let era = days / 146097;
+    let day_of_era = days % 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+// The moves have to start from the initial position.
+pub fn pgn(headers: &[(&str, String)], moves: &[(GameState, Move)], result: &str) -> String {
+    annotated_pgn(headers, moves, &[], result)
+}
+
+// Every move may be followed by a suffix like "?!" and a comment. The tags of
+// the seven tag roster missing in the headers are filled in, with "?" for the
+// unknown ones.
+pub fn annotated_pgn(
+    headers: &[(&str, String)],
+    moves: &[(GameState, Move)],
+    annotations: &[(String, Option<String>)],
+    result: &str,
+) -> String {
+    let mut text = String::new();
+    let mut tag = |name: &str, value: &str| {
+        text += &format!("[{} \"{}\"]\n", name, value.replace('"', "'"));
+    };
+    for name in SEVEN_TAG_ROSTER {
+        match headers.iter().find(|(header, _)| *header == name) {
+            _ if name == "Result" => tag(name, result),
+            Some((_, value)) => tag(name, value),
+            None if name == "Date" => tag(name, &pgn_date()),
+            None => tag(name, "?"),
+        }
+    }
+    for (name, value) in headers {
+        if !SEVEN_TAG_ROSTER.contains(name) {
+            tag(name, value);
+        }
+    }
+    text += "\n";
+    let mut tokens = Vec::new();
+    let mut after_comment = false;
+    for (ply, (game_state, played_move)) in moves.iter().enumerate() {
+        let number = ply / 2 + 1;
+        if game_state.now_moves == PieceColor::White {
+            tokens.push(format!("{}.", number));
+        } else if ply == 0 || after_comment {
+            tokens.push(format!("{}...", number));
+        }
+        let (suffix, comment) = match annotations.get(ply) {
+            Some((suffix, comment)) => (suffix.as_str(), comment.as_deref()),
+            None => ("", None),
+        };
+        tokens.push(san(game_state, *played_move) + suffix);
+        after_comment = comment.is_some();
+        if let Some(comment) = comment {
+            let words = format!("{{{}}}", comment.replace('}', ")"));
+            tokens.extend(words.split_whitespace().map(String::from));
+        }
+    }
+    tokens.push(result.to_string());
+    let mut line = String::new();
+    for token in tokens {
+        if !line.is_empty() && line.len() + 1 + token.len() > LINE_LENGTH {
+            text += &line;
+            text += "\n";
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line += &token;
+    }
+    text += &line;
+    text += "\n\n";
+    text
+}
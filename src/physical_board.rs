@@ -1,17 +1,25 @@
+use crate::animation::*;
 use crate::common::*;
 use crate::game_textures::*;
+use crate::piece::*;
+use crate::spawn_piece::*;
 use bevy::prelude::*;
 
 fn delete_piece_physically(
     commands: &mut Commands,
     query: &mut Query<(Entity, &mut Position, &mut Transform, &mut Handle<Image>)>,
+    animation: &Animation,
     position: Position,
 ) {
     for (entity, piece_position, _transform, _texture) in query.iter_mut() {
         if *piece_position != position {
             continue;
         }
-        commands.entity(entity).despawn();
+        if animation.duration > 0. {
+            commands.entity(entity).remove::<Position>().insert(FadeOut);
+        } else {
+            commands.entity(entity).despawn();
+        }
     }
 }
 
@@ -19,12 +27,13 @@ pub fn move_piece_physically(
     game_textures: &mut Res<GameTextures>,
     commands: &mut Commands,
     query: &mut Query<(Entity, &mut Position, &mut Transform, &mut Handle<Image>)>,
+    animation: &mut Animation,
     from: Position,
     to: Position,
     promote: bool,
 ) {
-    delete_piece_physically(commands, query, to);
-    for (_entity, mut piece_position, mut transform, mut texture) in query.iter_mut() {
+    delete_piece_physically(commands, query, animation, to);
+    for (entity, mut piece_position, mut transform, mut texture) in query.iter_mut() {
         if *piece_position != from {
             continue;
         }
@@ -36,6 +45,32 @@ pub fn move_piece_physically(
             }
         }
         *piece_position = to;
-        transform.translation = real_piece_position(to);
+        if animation.duration > 0. {
+            commands.entity(entity).insert(MoveAnimation {
+                start: transform.translation,
+                end: real_piece_position(to),
+            });
+            animation.time_left = animation.duration;
+        } else {
+            transform.translation = real_piece_position(to);
+        }
+    }
+}
+
+pub fn respawn_pieces(
+    game_textures: &GameTextures,
+    commands: &mut Commands,
+    query_pieces: &Query<Entity, With<Position>>,
+    board: &Board,
+) {
+    for entity in query_pieces.iter() {
+        commands.entity(entity).despawn();
+    }
+    for piece in board.iter().flatten().flatten() {
+        spawn_piece_sprite(
+            commands,
+            game_textures.piece_texture(piece.piece_color, piece.piece_type),
+            Position(piece.x, piece.y),
+        );
     }
 }
@@ -28,6 +28,46 @@ pub struct Piece {
 
 pub type Board = [[Option<Piece>; 8]; 8];
 
+const BACK_RANK: [PieceType; 8] = [
+    PieceType::Rook,
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Queen,
+    PieceType::King,
+    PieceType::Bishop,
+    PieceType::Knight,
+    PieceType::Rook,
+];
+
+pub fn starting_board() -> Board {
+    let mut board = [[None; 8]; 8];
+    for (x, piece_type) in BACK_RANK.iter().enumerate() {
+        for (piece_color, piece_type, y) in [
+            (PieceColor::White, *piece_type, 0),
+            (PieceColor::White, PieceType::Pawn, 1),
+            (PieceColor::Black, PieceType::Pawn, 6),
+            (PieceColor::Black, *piece_type, 7),
+        ] {
+            board[x][y] = Some(Piece {
+                piece_color,
+                piece_type,
+                x: x as i8,
+                y: y as i8,
+            });
+        }
+    }
+    board
+}
+
+impl PieceColor {
+    pub fn opposite(&self) -> PieceColor {
+        match self {
+            PieceColor::White => PieceColor::Black,
+            PieceColor::Black => PieceColor::White,
+        }
+    }
+}
+
 impl Piece {
     fn table_position(&self) -> Position {
         match self.piece_color {
@@ -35,29 +75,43 @@ impl Piece {
             PieceColor::Black => Position(self.y, self.x),
         }
     }
-    pub fn value(&self, is_endgame: bool) -> f32 {
+    // The bonus of the piece square tables, from the point of view of the owner.
+    pub fn table_value(&self, is_endgame: bool) -> f32 {
         let Position(x, y) = self.table_position();
         let (x, y) = (x as usize, y as usize);
         let value = match self.piece_type {
             PieceType::King => {
-                INFINITY
-                    + if is_endgame {
-                        TABLE_KING_END_GAME[x][y] as f32
-                    } else {
-                        TABLE_KING_MIDDLE_GAME[x][y] as f32
-                    }
+                if is_endgame {
+                    TABLE_KING_END_GAME[x][y]
+                } else {
+                    TABLE_KING_MIDDLE_GAME[x][y]
+                }
             }
-            PieceType::Queen => 900. + TABLE_QUEEN[x][y] as f32,
-            PieceType::Rook => 500. + TABLE_ROOK[x][y] as f32,
-            PieceType::Bishop => 330. + TABLE_BISHOP[x][y] as f32,
-            PieceType::Knight => 320. + TABLE_KNIGHT[x][y] as f32,
-            PieceType::Pawn => 100. + TABLE_PAWN[x][y] as f32,
+            PieceType::Queen => TABLE_QUEEN[x][y],
+            PieceType::Rook => TABLE_ROOK[x][y],
+            PieceType::Bishop => TABLE_BISHOP[x][y],
+            PieceType::Knight => TABLE_KNIGHT[x][y],
+            PieceType::Pawn => TABLE_PAWN[x][y],
         };
+        value as f32
+    }
+    pub fn signed(&self, value: f32) -> f32 {
         match self.piece_color {
             PieceColor::White => value,
             PieceColor::Black => -value,
         }
     }
+    pub fn value(&self, is_endgame: bool) -> f32 {
+        let value = match self.piece_type {
+            PieceType::King => INFINITY,
+            PieceType::Queen => 900.,
+            PieceType::Rook => 500.,
+            PieceType::Bishop => 330.,
+            PieceType::Knight => 320.,
+            PieceType::Pawn => 100.,
+        };
+        self.signed(value + self.table_value(is_endgame))
+    }
     fn is_legal(&self, x: i8, y: i8, board: Board) -> bool {
         if !(0..8).contains(&x) || !(0..8).contains(&y) {
             return false;
@@ -194,6 +248,32 @@ impl Piece {
             PieceType::Pawn => self.generate_pawn_moves(board),
         }
     }
+    // The squares attacked or defended by the piece, regardless of what stands on
+    // them. Unlike the moves, pawns attack only diagonally and kings never castle.
+    pub fn generate_attacks(&self, mut board: Board) -> Vec<Position> {
+        if self.piece_type == PieceType::Pawn {
+            let direction = match self.piece_color {
+                PieceColor::White => 1,
+                PieceColor::Black => -1,
+            };
+            return [-1, 1]
+                .into_iter()
+                .map(|dx| Position(self.x + dx, self.y + direction))
+                .filter(|Position(x, y)| (0..8).contains(x) && (0..8).contains(y))
+                .collect();
+        }
+        // The own pieces become capturable, so that defending them counts. Without
+        // an own rook the king does not castle either.
+        for piece in board.iter_mut().flatten().flatten() {
+            if piece.piece_color == self.piece_color && (piece.x, piece.y) != (self.x, self.y) {
+                piece.piece_color = self.piece_color.opposite();
+            }
+        }
+        self.generate_legal_moves(board)
+            .into_iter()
+            .map(|(_from, to)| to)
+            .collect()
+    }
     pub fn move_piece(&mut self, x: i8, y: i8) {
         self.x = x;
         self.y = y;
@@ -1,15 +1,138 @@
-const HELP_MESSAGE: &str = "Usage:\ncargo run --release -- x y\nx - number of human players (optional, default = 1)\ny - AI search depth (optional, default = 6)";
+use crate::clock::*;
+use crate::elo::*;
+use crate::piece::*;
+
+const HELP_MESSAGE: &str = "Usage:\ncargo run --release -- [options] x y\nx - number of human players (optional, default = 1)\ny - AI search depth (optional, default = 6)\nOptions:\n--book file - Polyglot opening book used by the AI (optional)\n--level n - AI difficulty level from 1 to 10, overrides the search depth (optional)\n--seed n - seed of all the randomness, printed at startup (optional, random by default)\n--color white|black|random - color of the human player, the board is flipped for Black (optional, default = random)\n--animation ms - duration of piece animations in milliseconds, 0 turns them off (optional, default = 200)\n--time-control m+s|Ns - chess clocks with m minutes and an increment of s seconds, or N seconds per move (optional, no clocks by default)\n--tui - play in the terminal instead of a window (optional)\n--match n - play n games between two engines without a window and report the Elo difference, the maximal number of games with --sprt (optional)\n--sprt elo0,elo1[,alpha,beta] - play games between two engines until a sequential probability ratio test decides between the Elo differences elo0 and elo1 (optional, alpha = beta = 0.05 by default)\n--openings file - openings of a match, one line of moves per opening played with both colors (optional, random openings by default)\n--engine1 spec, --engine2 spec - engines of a match like depth=3, level=5 or depth=3,eval=params.txt (optional, default = depth=y)\n--pgn file - file the games of a match or the reviewed game are written to (optional, default = match.pgn or review.pgn)\n--analysis - move both sides freely while the engine analyzes the position, deepening the search until the next move (optional)\n--analysis-depth n - maximal depth of the analysis (optional, unlimited by default)\n--multipv n - number of best lines shown in the analysis mode (optional, default = 3)\n--puzzles file - solve the puzzles of a CSV file in the Lichess format, the progress is stored in puzzle_progress.txt, not with --analysis (optional)\n--uci - run as a UCI engine on the standard input and output (optional)";
 pub static mut NUMBER_OF_PLAYERS: i32 = 1;
 pub static mut DEPTH: i32 = 6;
+pub static mut BOOK_PATH: Option<&'static str> = None;
+pub static mut LEVEL: Option<i32> = None;
+pub static mut SEED: Option<u64> = None;
+pub static mut COLOR: Option<PieceColor> = None;
+pub static mut ANIMATION_MS: u64 = 200;
+pub static mut TIME_CONTROL: Option<TimeControl> = None;
+pub static mut TUI: bool = false;
+pub static mut MATCH_GAMES: Option<u32> = None;
+pub static mut ENGINE1: Option<&'static str> = None;
+pub static mut ENGINE2: Option<&'static str> = None;
+pub static mut PGN_PATH: Option<&'static str> = None;
+pub static mut SPRT: Option<Sprt> = None;
+pub static mut ANALYSIS: bool = false;
+pub static mut ANALYSIS_DEPTH: Option<i32> = None;
+pub static mut MULTI_PV: usize = 3;
+pub static mut UCI: bool = false;
+pub static mut PUZZLES_PATH: Option<&'static str> = None;
+pub static mut OPENINGS_PATH: Option<&'static str> = None;
 
-fn print_help() {
+fn print_help() -> ! {
     println!("{}", HELP_MESSAGE);
     std::process::exit(0);
 }
 
+fn option_value(args: &mut impl Iterator<Item = String>) -> &'static str {
+    match args.next() {
+        Some(value) => Box::leak(value.into_boxed_str()),
+        None => print_help(),
+    }
+}
+
 pub fn program_options(args: Vec<String>) {
-    if args.len() >= 2 {
-        if let Ok(x) = args[1].parse::<i32>() {
+    let mut positional_args = Vec::new();
+    let mut args = args.into_iter().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--book" => unsafe {
+                BOOK_PATH = Some(option_value(&mut args));
+            },
+            "--level" => match option_value(&mut args).parse::<i32>() {
+                Ok(level) if (1..=10).contains(&level) => unsafe {
+                    LEVEL = Some(level);
+                },
+                _ => print_help(),
+            },
+            "--seed" => match option_value(&mut args).parse::<u64>() {
+                Ok(seed) => unsafe {
+                    SEED = Some(seed);
+                },
+                _ => print_help(),
+            },
+            "--color" => match option_value(&mut args) {
+                "white" => unsafe {
+                    COLOR = Some(PieceColor::White);
+                },
+                "black" => unsafe {
+                    COLOR = Some(PieceColor::Black);
+                },
+                "random" => unsafe {
+                    COLOR = None;
+                },
+                _ => print_help(),
+            },
+            "--animation" => match option_value(&mut args).parse::<u64>() {
+                Ok(animation_ms) => unsafe {
+                    ANIMATION_MS = animation_ms;
+                },
+                _ => print_help(),
+            },
+            "--time-control" => match TimeControl::parse(option_value(&mut args)) {
+                Some(time_control) => unsafe {
+                    TIME_CONTROL = Some(time_control);
+                },
+                None => print_help(),
+            },
+            "--tui" => unsafe {
+                TUI = true;
+            },
+            "--match" => match option_value(&mut args).parse::<u32>() {
+                Ok(games) if games > 0 => unsafe {
+                    MATCH_GAMES = Some(games);
+                },
+                _ => print_help(),
+            },
+            "--engine1" => unsafe {
+                ENGINE1 = Some(option_value(&mut args));
+            },
+            "--engine2" => unsafe {
+                ENGINE2 = Some(option_value(&mut args));
+            },
+            "--sprt" => match Sprt::parse(option_value(&mut args)) {
+                Some(sprt) => unsafe {
+                    SPRT = Some(sprt);
+                },
+                None => print_help(),
+            },
+            "--openings" => unsafe {
+                OPENINGS_PATH = Some(option_value(&mut args));
+            },
+            "--analysis" => unsafe {
+                ANALYSIS = true;
+            },
+            "--analysis-depth" => match option_value(&mut args).parse::<i32>() {
+                Ok(depth) if depth > 0 => unsafe {
+                    ANALYSIS_DEPTH = Some(depth);
+                },
+                _ => print_help(),
+            },
+            "--multipv" => match option_value(&mut args).parse::<usize>() {
+                Ok(lines) if lines > 0 => unsafe {
+                    MULTI_PV = lines;
+                },
+                _ => print_help(),
+            },
+            "--puzzles" => unsafe {
+                PUZZLES_PATH = Some(option_value(&mut args));
+            },
+            "--uci" => unsafe {
+                UCI = true;
+            },
+            "--pgn" => unsafe {
+                PGN_PATH = Some(option_value(&mut args));
+            },
+            _ => positional_args.push(arg),
+        }
+    }
+    if !positional_args.is_empty() {
+        if let Ok(x) = positional_args[0].parse::<i32>() {
             unsafe {
                 NUMBER_OF_PLAYERS = x;
             }
@@ -17,8 +140,8 @@ pub fn program_options(args: Vec<String>) {
             print_help();
         }
     }
-    if args.len() >= 3 {
-        if let Ok(x) = args[2].parse::<i32>() {
+    if positional_args.len() >= 2 {
+        if let Ok(x) = positional_args[1].parse::<i32>() {
             unsafe {
                 DEPTH = x;
             }
@@ -26,7 +149,25 @@ pub fn program_options(args: Vec<String>) {
             print_help();
         }
     }
-    if args.len() >= 4 {
+    if positional_args.len() >= 3 {
         print_help();
     }
+    // The puzzle trainer and the analysis mode both take over the board.
+    if unsafe { ANALYSIS } && unsafe { PUZZLES_PATH }.is_some() {
+        print_help();
+    }
+    // Both sides are moved by hand in the analysis mode, the engine only
+    // analyzes.
+    if unsafe { ANALYSIS } {
+        unsafe {
+            NUMBER_OF_PLAYERS = 2;
+        }
+    }
+    // The player solves the puzzles against the replies of the trainer, which
+    // take the place of the engine.
+    if unsafe { PUZZLES_PATH }.is_some() {
+        unsafe {
+            NUMBER_OF_PLAYERS = 1;
+        }
+    }
 }
@@ -0,0 +1,405 @@
+use crate::animation::*;
+use crate::buttons::*;
+use crate::common::*;
+use crate::elo::*;
+use crate::game_state::*;
+use crate::game_status::*;
+use crate::game_textures::*;
+use crate::move_history::*;
+use crate::notation::*;
+use crate::physical_board::*;
+use crate::piece::*;
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+pub const PUZZLE_PROGRESS: &str = "puzzle_progress.txt";
+const INITIAL_RATING: f64 = 1500.;
+const RATING_CHANGE: f64 = 32.;
+
+// The first move of the solution is the one of the opponent, the player has
+// to find every second move after it.
+pub struct Puzzle {
+    pub id: String,
+    pub game_state: GameState,
+    pub moves: Vec<Move>,
+    pub rating: f64,
+}
+
+// A line of the Lichess puzzle database like
+// "PuzzleId,FEN,Moves,Rating,...". The moves are in coordinates, a solution
+// with an under-promotion can not be played and is rejected.
+fn parse_puzzle(line: &str) -> Result<Puzzle, String> {
+    let fields = line.split(',').collect::<Vec<&str>>();
+    if fields.len() < 4 {
+        return Err(String::from("missing fields"));
+    }
+    let game_state = parse_fen(fields[1])?;
+    let mut line_state = game_state.clone();
+    let mut moves = Vec::new();
+    for text in fields[2].split_whitespace() {
+        let (from, to) = parse_coordinates(&line_state, text)
+            .ok_or_else(|| format!("unsupported move {}", text))?;
+        line_state.move_piece(from, to, true);
+        moves.push((from, to));
+    }
+    if moves.is_empty() || moves.len() % 2 != 0 {
+        return Err(format!("{} moves in the solution", moves.len()));
+    }
+    Ok(Puzzle {
+        id: fields[0].to_string(),
+        game_state,
+        moves,
+        rating: fields[3]
+            .parse()
+            .map_err(|_| format!("invalid rating {}", fields[3]))?,
+    })
+}
+
+pub fn load_puzzles(path: &str) -> io::Result<Vec<Puzzle>> {
+    let mut puzzles = Vec::new();
+    for (number, line) in fs::read_to_string(path)?.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with("PuzzleId") {
+            continue;
+        }
+        match parse_puzzle(line) {
+            Ok(puzzle) => puzzles.push(puzzle),
+            Err(error) => println!("Skipped the puzzle on line {}: {}", number + 1, error),
+        }
+    }
+    Ok(puzzles)
+}
+
+// The rating of the player and the attempted puzzles, stored in lines like
+// "rating 1500", "solved <id>" and "failed <id>".
+pub struct PuzzleProgress {
+    pub rating: f64,
+    pub attempts: HashMap<String, bool>,
+}
+
+impl PuzzleProgress {
+    pub fn load(path: &str) -> PuzzleProgress {
+        let mut progress = PuzzleProgress {
+            rating: INITIAL_RATING,
+            attempts: HashMap::new(),
+        };
+        let text = fs::read_to_string(path).unwrap_or_default();
+        for line in text.lines() {
+            match line.split_once(' ') {
+                Some(("rating", rating)) => {
+                    progress.rating = rating.trim().parse().unwrap_or(INITIAL_RATING)
+                }
+                Some(("solved", id)) => {
+                    progress.attempts.insert(id.trim().to_string(), true);
+                }
+                Some(("failed", id)) => {
+                    progress.attempts.insert(id.trim().to_string(), false);
+                }
+                _ => {}
+            }
+        }
+        progress
+    }
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut text = format!("rating {:.0}\n", self.rating);
+        let mut attempts = self.attempts.iter().collect::<Vec<(&String, &bool)>>();
+        attempts.sort();
+        for (id, solved) in attempts {
+            text += &format!("{} {}\n", if *solved { "solved" } else { "failed" }, id);
+        }
+        fs::write(path, text)
+    }
+    pub fn is_attempted(&self, id: &str) -> bool {
+        self.attempts.contains_key(id)
+    }
+    // The rating changes like in a game against a player of the puzzle
+    // rating.
+    pub fn record(&mut self, puzzle: &Puzzle, solved: bool) {
+        let expected_score = score_from_elo(self.rating - puzzle.rating);
+        let score = if solved { 1. } else { 0. };
+        self.rating += RATING_CHANGE * (score - expected_score);
+        self.attempts.insert(puzzle.id.clone(), solved);
+    }
+}
+
+// The puzzles are loaded in the background. Once none is left, is_done keeps
+// them from being searched again every frame.
+pub struct PuzzleTrainer {
+    pub puzzles: Vec<Puzzle>,
+    loading: Option<Arc<Mutex<Option<io::Result<Vec<Puzzle>>>>>>,
+    pub progress: PuzzleProgress,
+    pub current: Option<usize>,
+    pub is_failed: bool,
+    pub is_finished: bool,
+    pub is_done: bool,
+    pub message: String,
+}
+
+impl PuzzleTrainer {
+    pub fn new(path: &'static str) -> PuzzleTrainer {
+        let loading = Arc::new(Mutex::new(None));
+        let loaded = loading.clone();
+        thread::spawn(move || *loaded.lock().unwrap() = Some(load_puzzles(path)));
+        PuzzleTrainer {
+            puzzles: Vec::new(),
+            loading: Some(loading),
+            progress: PuzzleProgress::load(PUZZLE_PROGRESS),
+            current: None,
+            is_failed: false,
+            is_finished: false,
+            is_done: false,
+            message: String::from("Loading the puzzles ..."),
+        }
+    }
+    // The puzzle not attempted yet with the rating closest to the one of the
+    // player.
+    fn next_puzzle(&self) -> Option<usize> {
+        self.puzzles
+            .iter()
+            .enumerate()
+            .filter(|(_, puzzle)| !self.progress.is_attempted(&puzzle.id))
+            .min_by(|(_, a), (_, b)| {
+                let distance = |puzzle: &Puzzle| (puzzle.rating - self.progress.rating).abs();
+                distance(a).total_cmp(&distance(b))
+            })
+            .map(|(index, _)| index)
+    }
+    // Only the first result of every puzzle changes the rating.
+    fn record(&mut self, solved: bool) {
+        let puzzle = match self.current {
+            Some(current) => &self.puzzles[current],
+            None => return,
+        };
+        if self.progress.is_attempted(&puzzle.id) {
+            return;
+        }
+        self.progress.record(puzzle, solved);
+        if let Err(error) = self.progress.save(PUZZLE_PROGRESS) {
+            println!("Could not save {}: {}", PUZZLE_PROGRESS, error);
+        }
+    }
+    fn text(&self) -> String {
+        let mut text = format!("Your puzzle rating: {:.0}\n", self.progress.rating);
+        if let Some(current) = self.current {
+            let puzzle = &self.puzzles[current];
+            let solver = match puzzle.game_state.now_moves.opposite() {
+                PieceColor::White => "White",
+                PieceColor::Black => "Black",
+            };
+            text += &format!(
+                "Puzzle {} (rating {:.0})\nFind the best moves for {}\n",
+                puzzle.id, puzzle.rating, solver
+            );
+        }
+        text + &self.message
+    }
+}
+
+#[derive(Component)]
+pub struct PuzzleText;
+
+pub fn create_puzzle_panel(
+    mut commands: Commands,
+    game_textures: Res<GameTextures>,
+    puzzle_trainer: Option<Res<PuzzleTrainer>>,
+) {
+    if puzzle_trainer.is_none() {
+        return;
+    }
+    commands
+        .spawn_bundle(
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: game_textures.font.clone(),
+                    font_size: 18.,
+                    color: Color::WHITE,
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(10.),
+                    top: Val::Px(390.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+        )
+        .insert(PuzzleText);
+}
+
+pub fn puzzle_text_system(
+    puzzle_trainer: Option<Res<PuzzleTrainer>>,
+    mut query_text: Query<&mut Text, With<PuzzleText>>,
+) {
+    let puzzle_trainer = match puzzle_trainer {
+        Some(puzzle_trainer) if puzzle_trainer.is_changed() => puzzle_trainer,
+        _ => return,
+    };
+    let text = puzzle_trainer.text();
+    for mut puzzle_text in query_text.iter_mut() {
+        puzzle_text.sections[0].value = text.clone();
+    }
+}
+
+// The replies of the opponent come from the solution, a wrong move is taken
+// back and the puzzle counts as failed. Ctrl+N or the New game button go to
+// the next puzzle.
+pub fn puzzle_system(
+    keys: Res<Input<KeyCode>>,
+    query_buttons: Query<(&Interaction, &ActionButton), Changed<Interaction>>,
+    game_textures: Res<GameTextures>,
+    mut commands: Commands,
+    query: Query<(Entity, &mut Position, &mut Transform, &mut Handle<Image>)>,
+    query_pieces: Query<Entity, With<Position>>,
+    puzzle_trainer: Option<ResMut<PuzzleTrainer>>,
+    mut game_state: ResMut<GameState>,
+    mut move_history: ResMut<MoveHistory>,
+    mut selected_square: ResMut<SelectedSquare>,
+    mut animation: ResMut<Animation>,
+) {
+    let mut puzzle_trainer = match puzzle_trainer {
+        Some(puzzle_trainer) => puzzle_trainer,
+        None => return,
+    };
+    if let Some(loading) = &puzzle_trainer.loading {
+        let loaded = loading.lock().unwrap().take();
+        match loaded {
+            None => return,
+            Some(Ok(puzzles)) => {
+                puzzle_trainer.puzzles = puzzles;
+                puzzle_trainer.message.clear();
+            }
+            Some(Err(error)) => {
+                puzzle_trainer.message = format!("Could not load the puzzles: {}", error);
+                puzzle_trainer.is_done = true;
+            }
+        }
+        puzzle_trainer.loading = None;
+    }
+    if animation.is_running() {
+        return;
+    }
+    let next = (ctrl_pressed(&keys) && keys.just_pressed(KeyCode::N))
+        || is_clicked(&query_buttons, ActionButton::NewGame);
+    if next || (puzzle_trainer.current.is_none() && !puzzle_trainer.is_done) {
+        if next && !puzzle_trainer.is_finished {
+            puzzle_trainer.record(false);
+        }
+        let current = match puzzle_trainer.next_puzzle() {
+            Some(current) => current,
+            None => {
+                puzzle_trainer.is_done = true;
+                puzzle_trainer.message = String::from("All the puzzles are done");
+                return;
+            }
+        };
+        let puzzle_state = puzzle_trainer.puzzles[current].game_state.clone();
+        unsafe {
+            BOARD_FLIPPED = puzzle_state.now_moves == PieceColor::White;
+        }
+        *game_state = puzzle_state;
+        respawn_pieces(
+            &game_textures,
+            &mut commands,
+            &query_pieces,
+            &game_state.board,
+        );
+        move_history.clear();
+        selected_square.position = None;
+        puzzle_trainer.current = Some(current);
+        puzzle_trainer.is_failed = false;
+        puzzle_trainer.is_finished = false;
+        puzzle_trainer.message.clear();
+        return;
+    }
+    if game_state.player_moves || puzzle_trainer.is_finished {
+        return;
+    }
+    let solution = puzzle_trainer.puzzles[puzzle_trainer.current.unwrap()]
+        .moves
+        .clone();
+    let step = move_history.moves.len();
+    if step > 0 {
+        let (_, played_move) = move_history.moves[step - 1];
+        let is_mate = matches!(GameStatus::of(&game_state, None), GameStatus::Checkmate(_));
+        if played_move != solution[step - 1] && !is_mate {
+            puzzle_trainer.record(false);
+            puzzle_trainer.is_failed = true;
+            puzzle_trainer.message = String::from("Wrong move, try again");
+            move_history.undo(&mut game_state, None);
+            move_history.clear_undone();
+            respawn_pieces(
+                &game_textures,
+                &mut commands,
+                &query_pieces,
+                &game_state.board,
+            );
+            return;
+        }
+        if step == solution.len() || is_mate {
+            puzzle_trainer.record(true);
+            puzzle_trainer.is_finished = true;
+            puzzle_trainer.message = if puzzle_trainer.is_failed {
+                String::from("Solved after a mistake, Ctrl+N for the next puzzle")
+            } else {
+                String::from("Solved, Ctrl+N for the next puzzle")
+            };
+            return;
+        }
+        puzzle_trainer.message = String::from("Correct");
+    }
+    let (from, to) = solution[step];
+    game_state.computer_move(
+        game_textures,
+        &mut commands,
+        query,
+        &mut move_history,
+        None,
+        &mut animation,
+        from,
+        to,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(text: &str) -> Position {
+        let square = text.as_bytes();
+        Position((square[0] - b'a') as i8, (square[1] - b'1') as i8)
+    }
+
+    #[test]
+    fn parse_lichess_puzzle() {
+        let puzzle = parse_puzzle(
+            "00sHx,q3k1nr/1pp1nQpp/3p4/1P2p3/4P3/B1PP1b2/B5PP/5K2 b k - 0 17,\
+             e8d7 a2e6 d7d8 f7f8,1760,80,83,72,mate mateIn2 middlegame short",
+        )
+        .unwrap();
+        assert_eq!(puzzle.id, "00sHx");
+        assert_eq!(puzzle.rating, 1760.);
+        assert_eq!(puzzle.game_state.now_moves, PieceColor::Black);
+        assert_eq!(puzzle.moves.len(), 4);
+        assert_eq!(puzzle.moves[0], (position("e8"), position("d7")));
+        assert_eq!(puzzle.moves[3], (position("f7"), position("f8")));
+    }
+
+    #[test]
+    fn parse_invalid_puzzles() {
+        let fen = "7k/4P3/8/8/8/8/6r1/K7 b - - 0 1";
+        let puzzle = |moves: &str| parse_puzzle(&format!("id,{},{},1500", fen, moves));
+        assert!(puzzle("h8h7 e7e8q").is_ok());
+        assert!(puzzle("h8h7 e7e8n").is_err());
+        assert!(puzzle("h8h7").is_err());
+        assert!(puzzle("h8h7 e7e9").is_err());
+        assert!(puzzle("").is_err());
+        assert!(parse_puzzle(&format!("id,{},h8h7 e7e8q,rating", fen)).is_err());
+        assert!(parse_puzzle(&format!("id,{}", fen)).is_err());
+    }
+}
@@ -0,0 +1,245 @@
+use crate::buttons::*;
+use crate::clock::*;
+use crate::common::*;
+use crate::computer_moves::*;
+use crate::difficulty::*;
+use crate::game_state::*;
+use crate::game_status::*;
+use crate::keyboard_input::*;
+use crate::move_history::*;
+use crate::notation::*;
+use crate::pgn::*;
+use crate::piece::*;
+use crate::program_options::*;
+use crate::search::*;
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+pub const REVIEW_PGN: &str = "review.pgn";
+const REVIEW_TIME_PER_MOVE: Duration = Duration::from_millis(1000);
+// Centipawn losses from which a move is an inaccuracy, a mistake or a
+// blunder.
+const INACCURACY_LOSS: f32 = 50.;
+const MISTAKE_LOSS: f32 = 100.;
+const BLUNDER_LOSS: f32 = 300.;
+// Scores are capped before the losses are computed, so a missed mate counts as
+// a big loss and not an infinite one.
+const MAX_REVIEW_SCORE: f32 = 1500.;
+const SHOWN_LINE_MOVES: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveClass {
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl MoveClass {
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            MoveClass::Inaccuracy => "?!",
+            MoveClass::Mistake => "?",
+            MoveClass::Blunder => "??",
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            MoveClass::Inaccuracy => "Inaccuracy",
+            MoveClass::Mistake => "Mistake",
+            MoveClass::Blunder => "Blunder",
+        }
+    }
+}
+
+// The score after the played move, its loss against the best move in
+// centipawns and the best line when the played move was not the best one.
+pub struct MoveReview {
+    pub score: f32,
+    pub loss: f32,
+    pub best_line: Option<PvLine>,
+}
+
+impl MoveReview {
+    pub fn class(&self) -> Option<MoveClass> {
+        if self.loss >= BLUNDER_LOSS {
+            Some(MoveClass::Blunder)
+        } else if self.loss >= MISTAKE_LOSS {
+            Some(MoveClass::Mistake)
+        } else if self.loss >= INACCURACY_LOSS {
+            Some(MoveClass::Inaccuracy)
+        } else {
+            None
+        }
+    }
+    fn annotation(&self, game_state: &GameState) -> (String, Option<String>) {
+        let suffix = self.class().map_or("", |class| class.suffix());
+        let mut comment = format_score(self.score);
+        if let (Some(class), Some(best_line)) = (self.class(), &self.best_line) {
+            let mut line_state = game_state.clone();
+            let mut moves = Vec::new();
+            for &(from, to) in best_line.moves.iter().take(SHOWN_LINE_MOVES) {
+                moves.push(san(&line_state, (from, to)));
+                line_state.move_piece(from, to, true);
+            }
+            comment += &format!(
+                ". {}, best was {} ({})",
+                class.name(),
+                moves.join(" "),
+                format_score(best_line.score)
+            );
+        }
+        (suffix.to_string(), Some(comment))
+    }
+}
+
+pub fn review_difficulty() -> Difficulty {
+    let mut difficulty = Difficulty::from_depth(unsafe { DEPTH });
+    difficulty.time_limit = Some(REVIEW_TIME_PER_MOVE);
+    difficulty
+}
+
+pub fn review_move(
+    game_state: &GameState,
+    played_move: Move,
+    difficulty: &Difficulty,
+    rng: &mut StdRng,
+) -> MoveReview {
+    let (scored_moves, mut search) = deepen(game_state, difficulty, rng);
+    let best_score = scored_moves.first().map_or(0., |(_, score)| *score);
+    let score = scored_moves
+        .iter()
+        .find(|(scored_move, _)| *scored_move == played_move)
+        .map_or(best_score, |(_, score)| *score);
+    let capped = |score: f32| score.clamp(-MAX_REVIEW_SCORE, MAX_REVIEW_SCORE);
+    let loss = match game_state.now_moves {
+        PieceColor::White => capped(best_score) - capped(score),
+        PieceColor::Black => capped(score) - capped(best_score),
+    };
+    let best_line = if loss > 0. {
+        pv_lines(game_state, &scored_moves, 1, &mut search).pop()
+    } else {
+        None
+    };
+    MoveReview {
+        score,
+        loss,
+        best_line,
+    }
+}
+
+pub fn review_game(moves: &[(GameState, Move)], rng: &mut StdRng) -> Vec<MoveReview> {
+    let difficulty = review_difficulty();
+    moves
+        .iter()
+        .map(|(game_state, played_move)| review_move(game_state, *played_move, &difficulty, rng))
+        .collect()
+}
+
+// The number of inaccuracies, mistakes and blunders and the average loss of
+// both players.
+pub fn review_summary(moves: &[(GameState, Move)], reviews: &[MoveReview]) -> String {
+    let mut lines = Vec::new();
+    for (piece_color, name) in [(PieceColor::White, "White"), (PieceColor::Black, "Black")] {
+        let player_reviews = moves
+            .iter()
+            .zip(reviews)
+            .filter(|((game_state, _), _)| game_state.now_moves == piece_color)
+            .map(|(_, review)| review)
+            .collect::<Vec<&MoveReview>>();
+        let count = |class: MoveClass| {
+            player_reviews
+                .iter()
+                .filter(|review| review.class() == Some(class))
+                .count()
+        };
+        let total_loss = player_reviews.iter().map(|review| review.loss).sum::<f32>();
+        lines.push(format!(
+            "{}: {} inaccuracies, {} mistakes, {} blunders, average loss {:.0}",
+            name,
+            count(MoveClass::Inaccuracy),
+            count(MoveClass::Mistake),
+            count(MoveClass::Blunder),
+            total_loss / player_reviews.len().max(1) as f32
+        ));
+    }
+    lines.join("\n")
+}
+
+// Reviews the game and writes it annotated to the PGN file, returns the
+// summary of the review.
+pub fn write_review(moves: &[(GameState, Move)], result: &str, rng: &mut StdRng) -> String {
+    let reviews = review_game(moves, rng);
+    let annotations = moves
+        .iter()
+        .zip(&reviews)
+        .map(|((game_state, _), review)| review.annotation(game_state))
+        .collect::<Vec<(String, Option<String>)>>();
+    let headers = [("Event", String::from("Game review"))];
+    let text = annotated_pgn(&headers, moves, &annotations, result);
+    let path = unsafe { PGN_PATH }.unwrap_or(REVIEW_PGN);
+    let summary = review_summary(moves, &reviews);
+    match fs::write(path, text) {
+        Ok(()) => format!("{}\nReview written to {}", summary, path),
+        Err(error) => format!("{}\nCould not write {}: {}", summary, path, error),
+    }
+}
+
+// An unfinished game gets the result "*".
+pub fn game_result(game_state: &GameState, clock: Option<&Clock>) -> &'static str {
+    let status = GameStatus::of(game_state, clock);
+    if status.is_game_over() {
+        status.result()
+    } else {
+        "*"
+    }
+}
+
+// The review runs in the background, its summary is shown below the move
+// input when it is finished.
+#[derive(Default)]
+pub struct GameReview {
+    summary: Option<Arc<Mutex<Option<String>>>>,
+}
+
+pub fn review_system(
+    keys: Res<Input<KeyCode>>,
+    query_buttons: Query<(&Interaction, &ActionButton), Changed<Interaction>>,
+    game_state: Res<GameState>,
+    move_history: Res<MoveHistory>,
+    clock: Option<Res<Clock>>,
+    mut game_rng: ResMut<GameRng>,
+    mut game_review: ResMut<GameReview>,
+    mut move_input: ResMut<MoveInput>,
+) {
+    let finished_summary = match &game_review.summary {
+        Some(summary) => summary.lock().unwrap().take(),
+        None => None,
+    };
+    if let Some(summary) = finished_summary {
+        move_input.message = summary;
+        game_review.summary = None;
+    }
+    let start = (ctrl_pressed(&keys) && keys.just_pressed(KeyCode::R))
+        || is_clicked(&query_buttons, ActionButton::Review);
+    if !start || game_review.summary.is_some() {
+        return;
+    }
+    if move_history.moves.is_empty() {
+        move_input.message = String::from("No moves to review");
+        return;
+    }
+    move_input.message = String::from("Reviewing the game ...");
+    let moves = move_history.moves.clone();
+    let result = game_result(&game_state, clock.as_deref());
+    let mut rng = StdRng::seed_from_u64(game_rng.rng.gen());
+    let summary = Arc::new(Mutex::new(None));
+    game_review.summary = Some(summary.clone());
+    thread::spawn(move || {
+        *summary.lock().unwrap() = Some(write_review(&moves, result, &mut rng));
+    });
+}
@@ -0,0 +1,238 @@
+use crate::common::*;
+use crate::difficulty::*;
+use crate::eval_params::*;
+use crate::game_state::*;
+use crate::piece::*;
+use debug_print::debug_println;
+use rand::Rng;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+// Deeper iterations only happen in positions where a mate or the tablebases
+// end the search early, they would not change the result.
+pub const MAX_DEPTH: i32 = 64;
+
+pub struct Search {
+    pub cache: HashMap<(GameState, i32), f32>,
+    pub depth: i32,
+    pub deadline: Option<Instant>,
+    pub is_aborted: bool,
+    // Set from another thread to abort a search running in the background.
+    pub stop: Option<Arc<AtomicBool>>,
+    pub eval_params: EvalParams,
+    noise: f32,
+    noise_salt: u64,
+}
+
+impl Search {
+    pub fn new(depth: i32) -> Search {
+        Search {
+            cache: HashMap::new(),
+            depth,
+            deadline: None,
+            is_aborted: false,
+            stop: None,
+            eval_params: EvalParams::default(),
+            noise: 0.,
+            noise_salt: 0,
+        }
+    }
+    pub fn is_out_of_time(&mut self) -> bool {
+        if let Some(deadline) = self.deadline {
+            if Instant::now() >= deadline {
+                self.is_aborted = true;
+            }
+        }
+        if let Some(stop) = &self.stop {
+            if stop.load(Ordering::Relaxed) {
+                self.is_aborted = true;
+            }
+        }
+        self.is_aborted
+    }
+    // The noise depends only on the position, so cached and recomputed scores
+    // of the same position agree with each other.
+    pub fn noise(&self, game_state: &GameState) -> f32 {
+        if self.noise == 0. {
+            return 0.;
+        }
+        let mut hasher = DefaultHasher::new();
+        self.noise_salt.hash(&mut hasher);
+        game_state.hash(&mut hasher);
+        ((hasher.finish() % 2001) as f32 / 1000. - 1.) * self.noise
+    }
+}
+
+// Returns the legal moves with their scores, the best moves first.
+pub fn score_moves(game_state: &GameState, search: &mut Search) -> Vec<(Move, f32)> {
+    let score = game_state.evaluate(search.depth, search, -BIG_INFINITY, BIG_INFINITY);
+    let mut scored_moves = Vec::new();
+    for (from, to) in game_state.generate_legal_moves() {
+        let mut next_state = game_state.clone();
+        next_state.move_piece(from, to, true);
+        let next_state_score =
+            next_state.evaluate(search.depth - 1, search, -BIG_INFINITY, BIG_INFINITY);
+        debug_println!("(move, score) = ({:?}, {:?})", (from, to), next_state_score);
+        scored_moves.push(((from, to), next_state_score));
+    }
+    scored_moves.sort_by(|(_, a), (_, b)| match game_state.now_moves {
+        PieceColor::White => b.total_cmp(a),
+        PieceColor::Black => a.total_cmp(b),
+    });
+    debug_println!("score = {}", score);
+    debug_println!("cache size: {}", search.cache.len());
+    scored_moves
+}
+
+// The line starting with the given move, every reply is the best one at the
+// remaining depth. The scores mostly come from the cache of the last search.
+pub fn principal_variation(
+    game_state: &GameState,
+    first_move: Move,
+    search: &mut Search,
+) -> Vec<Move> {
+    let mut line = vec![first_move];
+    let mut line_state = game_state.clone();
+    line_state.move_piece(first_move.0, first_move.1, true);
+    for level in (1..search.depth).rev() {
+        // The line ends with a checkmate or a stalemate.
+        if line_state.generate_safe_moves().is_empty() {
+            break;
+        }
+        // Only a better reply needs an exact score, so the window narrows to
+        // the best score so far.
+        let mut best_move = None;
+        let mut best_score = match line_state.now_moves {
+            PieceColor::White => -BIG_INFINITY,
+            PieceColor::Black => BIG_INFINITY,
+        };
+        for (from, to) in line_state.generate_legal_moves() {
+            let mut next_state = line_state.clone();
+            next_state.move_piece(from, to, true);
+            let (alpha, beta) = match line_state.now_moves {
+                PieceColor::White => (best_score, BIG_INFINITY),
+                PieceColor::Black => (-BIG_INFINITY, best_score),
+            };
+            let score = next_state.evaluate(level - 1, search, alpha, beta);
+            let is_better = match line_state.now_moves {
+                PieceColor::White => score > best_score,
+                PieceColor::Black => score < best_score,
+            };
+            if best_move.is_none() || is_better {
+                best_move = Some((from, to));
+                best_score = score;
+            }
+        }
+        let (from, to) = match best_move {
+            Some(best_move) if !search.is_aborted => best_move,
+            _ => break,
+        };
+        line_state.move_piece(from, to, true);
+        line.push((from, to));
+    }
+    line
+}
+
+pub struct PvLine {
+    pub score: f32,
+    pub moves: Vec<Move>,
+}
+
+// The lines of the given number of best scored moves.
+pub fn pv_lines(
+    game_state: &GameState,
+    scored_moves: &[(Move, f32)],
+    lines: usize,
+    search: &mut Search,
+) -> Vec<PvLine> {
+    scored_moves
+        .iter()
+        .take(lines)
+        .map(|&(first_move, score)| PvLine {
+            score,
+            moves: principal_variation(game_state, first_move, search),
+        })
+        .collect()
+}
+
+// Deepens the search until it is stopped or the maximal depth is reached and
+// reports the best lines of every finished depth.
+pub fn deepen_lines(
+    game_state: &GameState,
+    search: &mut Search,
+    lines: usize,
+    max_depth: Option<i32>,
+    mut report: impl FnMut(i32, Vec<PvLine>),
+) {
+    for depth in 1..=max_depth.unwrap_or(MAX_DEPTH).min(MAX_DEPTH) {
+        search.depth = depth;
+        let scored_moves = score_moves(game_state, search);
+        if search.is_aborted || scored_moves.is_empty() {
+            return;
+        }
+        let depth_lines = pv_lines(game_state, &scored_moves, lines, search);
+        if search.is_aborted {
+            return;
+        }
+        report(depth, depth_lines);
+    }
+}
+
+// With a time limit the search is deepened iteratively and the result of the
+// deepest finished iteration is used. The first iteration is never aborted.
+// The returned search is set to the finished depth and can be continued.
+pub fn deepen<R: Rng>(
+    game_state: &GameState,
+    difficulty: &Difficulty,
+    rng: &mut R,
+) -> (Vec<(Move, f32)>, Search) {
+    debug_println!("Thinking ...");
+    let mut search = Search::new(difficulty.depth);
+    search.eval_params = difficulty.eval_params;
+    search.noise = difficulty.noise;
+    // Searches without noise leave the random generator untouched, so that they
+    // do not change the later moves of a seeded game.
+    if search.noise != 0. {
+        search.noise_salt = rng.gen();
+    }
+    let deadline = match difficulty.time_limit {
+        Some(time_limit) => Instant::now() + time_limit,
+        None => return (score_moves(game_state, &mut search), search),
+    };
+    let mut scored_moves = Vec::new();
+    let mut finished_depth = 0;
+    for depth in 1..=difficulty.depth {
+        search.depth = depth;
+        if depth > 1 {
+            search.deadline = Some(deadline);
+        }
+        let depth_scored_moves = score_moves(game_state, &mut search);
+        if search.is_aborted {
+            break;
+        }
+        debug_println!("finished depth {}", depth);
+        scored_moves = depth_scored_moves;
+        finished_depth = depth;
+    }
+    search.depth = finished_depth;
+    search.deadline = None;
+    search.is_aborted = false;
+    (scored_moves, search)
+}
+
+// The given number of best lines, the best one first, with the depth they were
+// searched to.
+pub fn multi_pv<R: Rng>(
+    game_state: &GameState,
+    difficulty: &Difficulty,
+    lines: usize,
+    rng: &mut R,
+) -> (Vec<PvLine>, i32) {
+    let (scored_moves, mut search) = deepen(game_state, difficulty, rng);
+    let lines = pv_lines(game_state, &scored_moves, lines, &mut search);
+    (lines, search.depth)
+}
@@ -1,13 +1,32 @@
+use crate::analysis::*;
+use crate::animation::*;
+use crate::clock::*;
 use crate::common::*;
+use crate::computer_moves::*;
+use crate::difficulty::*;
 use crate::game_state::*;
 use crate::game_textures::*;
+use crate::hint::*;
+use crate::keyboard_input::*;
+use crate::move_history::*;
+use crate::opening_book::*;
 use crate::piece::*;
 use crate::program_options::*;
+use crate::puzzle::*;
+use crate::review::*;
+use crate::theme::*;
+use crate::threats::*;
 use bevy::prelude::*;
 use debug_print::debug_println;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
-pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let game_textures = GameTextures {
+pub fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let mut game_textures = GameTextures {
         tilel: asset_server.load(TILEL_SPRITE),
         kingl: asset_server.load(KINGL_SPRITE),
         queenl: asset_server.load(QUEENL_SPRITE),
@@ -23,18 +42,112 @@ pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
         knightd: asset_server.load(KNIGHTD_SPRITE),
         pawnd: asset_server.load(PAWND_SPRITE),
         highlight: asset_server.load(HIGHLIGHT_SPRITE),
+        move_hint: images.add(move_hint_image()),
+        capture_hint: images.add(capture_hint_image()),
+        arrow_head: images.add(arrow_head_image()),
+        threat_ring: images.add(threat_ring_image()),
+        cursor_frame: images.add(cursor_frame_image()),
+        font: asset_server.load(FONT),
     };
+    let mut themes = Themes::load();
+    apply_board_theme(&mut game_textures, &mut themes, &asset_server, &mut images);
+    apply_piece_set(
+        &mut game_textures,
+        &themes.piece_sets[themes.piece_set],
+        &asset_server,
+    );
     commands.insert_resource(game_textures);
+    commands.insert_resource(themes);
 
     commands
         .spawn_bundle(Camera2dBundle::default())
         .insert(MainCamera);
 
-    commands.insert_resource(MousePosition { position: None });
+    commands.insert_resource(MousePosition {
+        position: None,
+        world_position: None,
+    });
 
     commands.insert_resource(SelectedSquare { position: None });
 
-    let mut player_moves = rand::random::<bool>();
+    commands.insert_resource(DraggedPiece { entity: None });
+
+    commands.insert_resource(MoveHistory::default());
+
+    commands.insert_resource(ShowThreats { is_enabled: false });
+
+    commands.insert_resource(MoveInput {
+        text: String::new(),
+        message: String::new(),
+        cursor: None,
+    });
+
+    commands.insert_resource(EngineInfo::Idle);
+
+    commands.insert_resource(GameReview::default());
+    commands.insert_resource(HintSearch::default());
+
+    commands.insert_resource(Animation {
+        duration: unsafe { ANIMATION_MS } as f32 / 1000.,
+        time_left: 0.,
+    });
+
+    if let Some(opening_book) = load_opening_book() {
+        commands.insert_resource(opening_book);
+    }
+
+    commands.insert_resource(initial_difficulty());
+
+    let (rng, human_color) = initial_rng_and_color();
+    if let Some(time_control) = unsafe { TIME_CONTROL } {
+        commands.insert_resource(Clock::new(time_control, PieceColor::White));
+    }
+    if let Some(path) = unsafe { PUZZLES_PATH } {
+        commands.insert_resource(PuzzleTrainer::new(path));
+    }
+    if unsafe { ANALYSIS } {
+        commands.insert_resource(Analysis::default());
+    }
+    commands.insert_resource(GameRng { rng });
+    commands.insert_resource(new_game_state(human_color));
+}
+
+// The setup shared by the frontends, driven by the program options.
+pub fn load_opening_book() -> Option<OpeningBook> {
+    let path = unsafe { BOOK_PATH }?;
+    match OpeningBook::load(path) {
+        Ok(opening_book) => Some(opening_book),
+        Err(error) => {
+            println!("Could not load the opening book {}: {}", path, error);
+            None
+        }
+    }
+}
+
+pub fn initial_difficulty() -> Difficulty {
+    match unsafe { LEVEL } {
+        Some(level) => Difficulty::from_level(level),
+        None => Difficulty::from_depth(unsafe { DEPTH }),
+    }
+}
+
+pub fn initial_rng_and_color() -> (StdRng, PieceColor) {
+    let seed = unsafe { SEED }.unwrap_or_else(rand::random::<u64>);
+    println!("Seed: {}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let human_color = unsafe { COLOR }.unwrap_or_else(|| {
+        if unsafe { NUMBER_OF_PLAYERS } == 1 && !rng.gen::<bool>() {
+            PieceColor::Black
+        } else {
+            PieceColor::White
+        }
+    });
+    (rng, human_color)
+}
+
+// An empty board with White to move, the pieces are spawned separately.
+pub fn new_game_state(human_color: PieceColor) -> GameState {
+    let mut player_moves = human_color == PieceColor::White;
     unsafe {
         if NUMBER_OF_PLAYERS == 0 {
             player_moves = false;
@@ -42,13 +155,14 @@ pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
         if NUMBER_OF_PLAYERS == 2 {
             player_moves = true;
         }
+        BOARD_FLIPPED = NUMBER_OF_PLAYERS != 0 && human_color == PieceColor::Black;
     }
     if player_moves {
         debug_println!("Your move");
     }
-    commands.insert_resource(GameState {
+    GameState {
         board: [[None; 8]; 8],
         now_moves: PieceColor::White,
         player_moves,
-    });
+    }
 }
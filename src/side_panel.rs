@@ -0,0 +1,144 @@
+use crate::clock::*;
+use crate::computer_moves::*;
+use crate::game_state::*;
+use crate::game_status::*;
+use crate::game_textures::*;
+use crate::move_history::*;
+use crate::notation::*;
+use crate::piece::*;
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+
+const SHOWN_MOVE_LINES: usize = 20;
+const BANNER_COLOR: Color = Color::rgba(0., 0., 0., 0.8);
+
+#[derive(Component)]
+pub struct SidePanel;
+
+#[derive(Component)]
+pub struct GameOverBanner;
+
+fn text_style(game_textures: &GameTextures, font_size: f32) -> TextStyle {
+    TextStyle {
+        font: game_textures.font.clone(),
+        font_size,
+        color: Color::WHITE,
+    }
+}
+
+pub fn create_side_panel(mut commands: Commands, game_textures: Res<GameTextures>) {
+    commands
+        .spawn_bundle(
+            TextBundle::from_section("", text_style(&game_textures, 20.)).with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    right: Val::Px(10.),
+                    top: Val::Px(70.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+        )
+        .insert(SidePanel);
+}
+
+// The moves in standard algebraic notation, a full move per line.
+fn move_lines(move_history: &MoveHistory) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (number, (game_state, history_move)) in move_history.moves.iter().enumerate() {
+        let san = san(game_state, *history_move);
+        if number == 0 && game_state.now_moves == PieceColor::Black {
+            lines.push(String::from("1. ... ") + &san);
+        } else if game_state.now_moves == PieceColor::White {
+            lines.push(format!("{}. {}", lines.len() + 1, san));
+        } else if let Some(line) = lines.last_mut() {
+            *line += &format!(" {}", san);
+        }
+    }
+    lines
+}
+
+fn spawn_banner(commands: &mut Commands, game_textures: &GameTextures, text: String) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            focus_policy: FocusPolicy::Pass,
+            ..Default::default()
+        })
+        .insert(GameOverBanner)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        padding: UiRect::all(Val::Px(20.)),
+                        ..Default::default()
+                    },
+                    color: BANNER_COLOR.into(),
+                    focus_policy: FocusPolicy::Pass,
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent.spawn_bundle(TextBundle::from_section(
+                        text,
+                        text_style(game_textures, 40.),
+                    ));
+                });
+        });
+}
+
+pub fn side_panel_system(
+    game_textures: Res<GameTextures>,
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    move_history: Res<MoveHistory>,
+    engine_info: Res<EngineInfo>,
+    clock: Option<Res<Clock>>,
+    mut query_panel: Query<&mut Text, With<SidePanel>>,
+    query_banner: Query<Entity, With<GameOverBanner>>,
+    mut lines: Local<Vec<String>>,
+    mut board_status: Local<Option<GameStatus>>,
+) {
+    if game_state.is_changed() || board_status.is_none() {
+        *lines = move_lines(&move_history);
+        *board_status = Some(GameStatus::of(&game_state, None));
+    }
+    let status = board_status.unwrap().with_clock(clock.as_deref());
+    let mut text = match game_state.now_moves {
+        PieceColor::White => String::from("White to move\n"),
+        PieceColor::Black => String::from("Black to move\n"),
+    };
+    if status != GameStatus::Ongoing {
+        text += &format!("{}\n", status.description());
+    }
+    if let Some(description) = engine_info.description() {
+        text += &format!("Engine: {}\n", description);
+    }
+    text += "\n";
+    for line in lines
+        .iter()
+        .skip(lines.len().saturating_sub(SHOWN_MOVE_LINES))
+    {
+        text += &format!("{}\n", line);
+    }
+    for mut panel_text in query_panel.iter_mut() {
+        if panel_text.sections[0].value != text {
+            panel_text.sections[0].value = text.clone();
+        }
+    }
+    if status.is_game_over() == query_banner.is_empty() {
+        if status.is_game_over() {
+            spawn_banner(&mut commands, &game_textures, status.description());
+        } else {
+            for entity in query_banner.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
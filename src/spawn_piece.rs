@@ -1,17 +1,8 @@
 use crate::common::*;
-use crate::game_state::*;
 use crate::game_textures::*;
-use crate::piece::*;
 use bevy::prelude::*;
 
-pub fn spawn_piece(
-    commands: &mut Commands,
-    texture: Handle<Image>,
-    position: Position,
-    piece_color: PieceColor,
-    piece_type: PieceType,
-    game_state: &mut ResMut<GameState>,
-) {
+pub fn spawn_piece_sprite(commands: &mut Commands, texture: Handle<Image>, position: Position) {
     let mut transform = Transform {
         translation: real_piece_position(position),
         ..Default::default()
@@ -25,11 +16,4 @@ pub fn spawn_piece(
             ..Default::default()
         })
         .insert(position);
-
-    game_state.board[position.0 as usize][position.1 as usize] = Some(Piece {
-        piece_color,
-        piece_type,
-        x: position.0,
-        y: position.1,
-    });
 }
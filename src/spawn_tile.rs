@@ -7,7 +7,7 @@ pub fn spawn_tile(
     texture: Handle<Image>,
     position: Position,
     highlight: bool,
-) {
+) -> Entity {
     let mut transform = Transform {
         translation: real_position(position),
         ..Default::default()
@@ -21,8 +21,33 @@ pub fn spawn_tile(
     if highlight {
         sprite.transform.translation += Vec3::new(0., 0., 0.5);
         sprite.sprite.color.set_a(0.7);
-        commands.spawn_bundle(sprite).insert(Highlight {});
-    } else {
-        commands.spawn_bundle(sprite);
     }
+    let mut entity_commands = commands.spawn_bundle(sprite);
+    entity_commands.insert(BoardSquare { position });
+    if !highlight {
+        entity_commands.insert(Tile {});
+    }
+    entity_commands.id()
+}
+
+pub fn spawn_move_hint(
+    commands: &mut Commands,
+    texture: Handle<Image>,
+    position: Position,
+) -> Entity {
+    let mut transform = Transform {
+        translation: real_position(position) + Vec3::new(0., 0., 1.5),
+        ..Default::default()
+    };
+    transform.scale *= SCALING_FACTOR;
+    let mut sprite = SpriteBundle {
+        texture,
+        transform,
+        ..Default::default()
+    };
+    sprite.sprite.color.set_a(0.3);
+    commands
+        .spawn_bundle(sprite)
+        .insert(BoardSquare { position })
+        .id()
 }
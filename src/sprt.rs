@@ -0,0 +1,45 @@
+use crate::elo::*;
+use crate::match_runner::*;
+
+// Plays color swapped pairs of games until the test accepts one of the
+// hypotheses or the maximal number of games is reached.
+pub fn run(sprt: Sprt, max_games: Option<u32>) {
+    let mut engine_match = EngineMatch::new();
+    let (lower, upper) = sprt.bounds();
+    println!(
+        "SPRT elo0 = {}, elo1 = {}, alpha = {}, beta = {}, bounds [{:.2}, {:.2}]",
+        sprt.elo0, sprt.elo1, sprt.alpha, sprt.beta, lower, upper
+    );
+    let mut round = 0;
+    let result = loop {
+        if matches!(max_games, Some(max_games) if round >= max_games) {
+            break SprtResult::Continue;
+        }
+        round += 1;
+        engine_match.play_round(round, "SPRT");
+        if round % 2 == 1 {
+            continue;
+        }
+        println!(
+            "LLR {:.2} after {} games",
+            engine_match
+                .score
+                .log_likelihood_ratio(sprt.elo0, sprt.elo1),
+            round
+        );
+        match sprt.test(&engine_match.score) {
+            SprtResult::Continue => {}
+            result => break result,
+        }
+    };
+    engine_match.print_score();
+    match result {
+        SprtResult::AcceptH0 => {
+            println!("H0 accepted, the Elo difference is at most {}", sprt.elo0)
+        }
+        SprtResult::AcceptH1 => {
+            println!("H1 accepted, the Elo difference is at least {}", sprt.elo1)
+        }
+        SprtResult::Continue => println!("Inconclusive after {} games", round),
+    }
+}
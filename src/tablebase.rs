@@ -0,0 +1,334 @@
+use crate::common::*;
+use crate::game_state::*;
+use crate::piece::*;
+use debug_print::debug_println;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+// Every table covers the positions with both kings and a single White piece,
+// there are no tables with four or more pieces.
+// Positions where Black owns the piece are probed with colors and ranks
+// mirrored. A value is the distance to mate in plies from the point of view of
+// the side to move: MATE - d means winning in d plies, -(MATE - d) means being
+// mated in d plies and 0 is a draw.
+const TABLEBASE_DIRECTORY: &str = "tablebases";
+const TABLE_SIZE: usize = 2 * 64 * 64 * 64;
+const MATE: i8 = 127;
+const ILLEGAL: i8 = i8::MIN;
+const EXTERNAL: u32 = 1 << 31;
+pub const TABLEBASE_WIN: f32 = INFINITY / 2.;
+pub const MAX_TABLEBASE_PIECES: usize = 3;
+// The pawn comes last, its table probes the one of the queen it promotes to.
+const TABLE_TYPES: [PieceType; 5] = [
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+    PieceType::Pawn,
+];
+
+type Tables = Vec<(PieceType, Vec<i8>)>;
+
+// Set by load_tables at startup, before any search. Without it, as in the
+// tests which do not load the tables, nothing is probed.
+static TABLES: OnceLock<Tables> = OnceLock::new();
+
+fn table_name(piece_type: PieceType) -> &'static str {
+    match piece_type {
+        PieceType::King => "KK",
+        PieceType::Queen => "KQK",
+        PieceType::Rook => "KRK",
+        PieceType::Bishop => "KBK",
+        PieceType::Knight => "KNK",
+        PieceType::Pawn => "KPK",
+    }
+}
+
+fn square(position: Position) -> usize {
+    (8 * position.0 + position.1) as usize
+}
+
+fn square_position(square: usize) -> Position {
+    Position((square / 8) as i8, (square % 8) as i8)
+}
+
+fn mirror(position: Position) -> Position {
+    Position(position.0, 7 - position.1)
+}
+
+fn place_piece(
+    board: &mut Board,
+    position: Position,
+    piece_color: PieceColor,
+    piece_type: PieceType,
+) {
+    board[position.0 as usize][position.1 as usize] = Some(Piece {
+        piece_color,
+        piece_type,
+        x: position.0,
+        y: position.1,
+    });
+}
+
+fn table_index(game_state: &GameState) -> Option<(PieceType, usize)> {
+    let mut white_king = None;
+    let mut black_king = None;
+    let mut extra_piece = None;
+    for i in 0..8 {
+        for j in 0..8 {
+            if let Some(piece) = game_state.board[i][j] {
+                match (piece.piece_type, piece.piece_color) {
+                    (PieceType::King, PieceColor::White) => {
+                        white_king = Some(Position(piece.x, piece.y))
+                    }
+                    (PieceType::King, PieceColor::Black) => {
+                        black_king = Some(Position(piece.x, piece.y))
+                    }
+                    _ => {
+                        if extra_piece.is_some() {
+                            return None;
+                        }
+                        extra_piece = Some(piece);
+                    }
+                }
+            }
+        }
+    }
+    let (white_king, black_king, piece) = (white_king?, black_king?, extra_piece?);
+    let piece_position = Position(piece.x, piece.y);
+    let (white_king, black_king, piece_position, now_moves) = match piece.piece_color {
+        PieceColor::White => (white_king, black_king, piece_position, game_state.now_moves),
+        PieceColor::Black => (
+            mirror(black_king),
+            mirror(white_king),
+            mirror(piece_position),
+            game_state.now_moves.opposite(),
+        ),
+    };
+    let side = match now_moves {
+        PieceColor::White => 0,
+        PieceColor::Black => 1,
+    };
+    let index =
+        ((side * 64 + square(white_king)) * 64 + square(black_king)) * 64 + square(piece_position);
+    Some((piece.piece_type, index))
+}
+
+fn table_game_state(piece_type: PieceType, index: usize) -> Option<GameState> {
+    let piece_position = square_position(index % 64);
+    let black_king = square_position(index / 64 % 64);
+    let white_king = square_position(index / (64 * 64) % 64);
+    let now_moves = match index / (64 * 64 * 64) {
+        0 => PieceColor::White,
+        _ => PieceColor::Black,
+    };
+    if white_king == black_king || white_king == piece_position || black_king == piece_position {
+        return None;
+    }
+    if piece_type == PieceType::Pawn && (piece_position.1 == 0 || piece_position.1 == 7) {
+        return None;
+    }
+    let mut board = [[None; 8]; 8];
+    place_piece(&mut board, white_king, PieceColor::White, PieceType::King);
+    place_piece(&mut board, black_king, PieceColor::Black, PieceType::King);
+    place_piece(&mut board, piece_position, PieceColor::White, piece_type);
+    Some(GameState {
+        board,
+        now_moves,
+        player_moves: false,
+    })
+}
+
+fn distance(value: i8) -> usize {
+    (MATE - value.abs()) as usize
+}
+
+// Retrograde analysis done in rounds: in round d every position winning or
+// losing in exactly d plies is resolved, using only the positions resolved in
+// the previous rounds. Positions left unresolved at the end are draws.
+fn generate(tables: &Tables, piece_type: PieceType) -> Vec<i8> {
+    debug_println!("Generating the {} tablebase ...", table_name(piece_type));
+    let mut values = vec![ILLEGAL; TABLE_SIZE];
+    let mut resolved = vec![false; TABLE_SIZE];
+    let mut successor_offsets = vec![0; TABLE_SIZE + 1];
+    let mut successors = Vec::<u32>::new();
+    let mut max_external_distance = 0;
+    for index in 0..TABLE_SIZE {
+        successor_offsets[index] = successors.len();
+        let game_state = match table_game_state(piece_type, index) {
+            Some(game_state) if !game_state.is_in_check(game_state.now_moves.opposite()) => {
+                game_state
+            }
+            _ => continue,
+        };
+        values[index] = 0;
+        for (from, to) in game_state.generate_legal_moves() {
+            let mut next_state = game_state.clone();
+            next_state.move_piece(from, to, true);
+            if next_state.is_in_check(game_state.now_moves) {
+                continue;
+            }
+            match table_index(&next_state) {
+                Some((next_piece_type, next_index)) if next_piece_type == piece_type => {
+                    successors.push(next_index as u32);
+                }
+                _ => {
+                    let value = probe_tables(tables, &next_state).unwrap_or(0);
+                    if value != 0 {
+                        max_external_distance = max_external_distance.max(distance(value));
+                    }
+                    successors.push(EXTERNAL | value as u8 as u32);
+                }
+            }
+        }
+        if successor_offsets[index] == successors.len() {
+            resolved[index] = true;
+            if game_state.is_in_check(game_state.now_moves) {
+                values[index] = -MATE;
+            }
+        }
+    }
+    successor_offsets[TABLE_SIZE] = successors.len();
+
+    for round in 1..MATE as usize {
+        let mut updates = Vec::new();
+        for index in 0..TABLE_SIZE {
+            if values[index] == ILLEGAL || resolved[index] {
+                continue;
+            }
+            let mut is_winning = false;
+            let mut is_losing = true;
+            let mut losing_distance = 0;
+            for &successor in &successors[successor_offsets[index]..successor_offsets[index + 1]] {
+                let value = if successor & EXTERNAL != 0 {
+                    successor as u8 as i8
+                } else if resolved[successor as usize] {
+                    values[successor as usize]
+                } else {
+                    is_losing = false;
+                    continue;
+                };
+                if value < 0 && distance(value) + 1 == round {
+                    is_winning = true;
+                }
+                if value > 0 {
+                    losing_distance = losing_distance.max(distance(value) + 1);
+                } else {
+                    is_losing = false;
+                }
+            }
+            if is_winning {
+                updates.push((index, MATE - round as i8));
+            } else if is_losing && losing_distance == round {
+                updates.push((index, -(MATE - round as i8)));
+            }
+        }
+        if updates.is_empty() && round > max_external_distance {
+            break;
+        }
+        for (index, value) in updates {
+            values[index] = value;
+            resolved[index] = true;
+        }
+    }
+    values
+}
+
+fn load(tables: &Tables, piece_type: PieceType) -> Vec<i8> {
+    let path = Path::new(TABLEBASE_DIRECTORY).join(format!("{}.tb", table_name(piece_type)));
+    if let Ok(bytes) = fs::read(&path) {
+        if bytes.len() == TABLE_SIZE {
+            return bytes.into_iter().map(|byte| byte as i8).collect();
+        }
+    }
+    let values = generate(tables, piece_type);
+    let bytes = values.iter().map(|value| *value as u8).collect::<Vec<u8>>();
+    if let Err(error) =
+        fs::create_dir_all(TABLEBASE_DIRECTORY).and_then(|_| fs::write(&path, bytes))
+    {
+        println!("Could not save the tablebase {}: {}", path.display(), error);
+    }
+    values
+}
+
+// Loads the tables, generating the missing ones, and waits until they are
+// ready.
+pub fn load_tables() {
+    TABLES.get_or_init(|| {
+        let mut tables = Tables::new();
+        for piece_type in TABLE_TYPES {
+            let values = load(&tables, piece_type);
+            tables.push((piece_type, values));
+        }
+        tables
+    });
+}
+
+fn is_bare_kings(game_state: &GameState) -> bool {
+    game_state
+        .board
+        .iter()
+        .flatten()
+        .flatten()
+        .all(|piece| piece.piece_type == PieceType::King)
+}
+
+fn probe_tables(tables: &Tables, game_state: &GameState) -> Option<i8> {
+    if is_bare_kings(game_state) {
+        return Some(0);
+    }
+    let (piece_type, index) = table_index(game_state)?;
+    let (_, values) = tables
+        .iter()
+        .find(|(table_type, _)| *table_type == piece_type)?;
+    match values[index] {
+        ILLEGAL => None,
+        value => Some(value),
+    }
+}
+
+pub fn probe(game_state: &GameState) -> Option<i8> {
+    probe_tables(TABLES.get()?, game_state)
+}
+
+pub fn probe_score(game_state: &GameState) -> Option<f32> {
+    let value = probe(game_state)?;
+    let score = match value {
+        0 => 0.,
+        value if value > 0 => TABLEBASE_WIN - distance(value) as f32,
+        value => -TABLEBASE_WIN + distance(value) as f32,
+    };
+    match game_state.now_moves {
+        PieceColor::White => Some(score),
+        PieceColor::Black => Some(-score),
+    }
+}
+
+pub fn best_moves(game_state: &GameState) -> Vec<Move> {
+    if probe(game_state).is_none() {
+        return Vec::new();
+    }
+    // The lower the value for the opponent, the better the move.
+    let scored_moves = game_state
+        .generate_legal_moves()
+        .into_iter()
+        .filter_map(|(from, to)| {
+            let mut next_state = game_state.clone();
+            next_state.move_piece(from, to, true);
+            if next_state.is_in_check(game_state.now_moves) {
+                return None;
+            }
+            probe(&next_state).map(|value| ((from, to), value))
+        })
+        .collect::<Vec<(Move, i8)>>();
+    let best_value = match scored_moves.iter().map(|(_, value)| *value).min() {
+        Some(value) => value,
+        None => return Vec::new(),
+    };
+    scored_moves
+        .into_iter()
+        .filter(|(_, value)| *value == best_value)
+        .map(|(tablebase_move, _)| tablebase_move)
+        .collect()
+}
@@ -0,0 +1,299 @@
+use crate::animation::*;
+use crate::common::*;
+use crate::difficulty::*;
+use crate::game_state::*;
+use crate::game_textures::*;
+use crate::physical_board::*;
+use crate::piece::*;
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+const ASSETS_DIRECTORY: &str = "assets";
+const THEMES_PATH: &str = "assets/themes.txt";
+const DEFAULT_PIECE_DIRECTORY: &str = "sprites";
+const FALLBACK_LIGHT_COLOR: [u8; 3] = [240, 217, 181];
+const FALLBACK_DARK_COLOR: [u8; 3] = [181, 136, 99];
+const PIECE_FILES: [(PieceColor, PieceType, &str); 12] = [
+    (PieceColor::White, PieceType::King, "kl.png"),
+    (PieceColor::White, PieceType::Queen, "ql.png"),
+    (PieceColor::White, PieceType::Rook, "rl.png"),
+    (PieceColor::White, PieceType::Bishop, "bl.png"),
+    (PieceColor::White, PieceType::Knight, "nl.png"),
+    (PieceColor::White, PieceType::Pawn, "pl.png"),
+    (PieceColor::Black, PieceType::King, "kd.png"),
+    (PieceColor::Black, PieceType::Queen, "qd.png"),
+    (PieceColor::Black, PieceType::Rook, "rd.png"),
+    (PieceColor::Black, PieceType::Bishop, "bd.png"),
+    (PieceColor::Black, PieceType::Knight, "nd.png"),
+    (PieceColor::Black, PieceType::Pawn, "pd.png"),
+];
+
+pub enum SquareStyle {
+    Texture(String),
+    Color([u8; 3]),
+}
+
+pub struct BoardTheme {
+    pub name: String,
+    pub light: SquareStyle,
+    pub dark: SquareStyle,
+}
+
+pub struct PieceSet {
+    pub name: String,
+    pub directory: String,
+}
+
+pub struct Themes {
+    pub board_themes: Vec<BoardTheme>,
+    pub piece_sets: Vec<PieceSet>,
+    pub board_theme: usize,
+    pub piece_set: usize,
+    // The images of the square colors, created once and shared by the themes.
+    pub solid_images: HashMap<[u8; 3], Handle<Image>>,
+}
+
+#[derive(Component)]
+pub struct ThemeMenu;
+
+fn parse_square_style(text: &str) -> Option<SquareStyle> {
+    match text.strip_prefix('#') {
+        Some(hex) if hex.len() == 6 => {
+            let mut color = [0; 3];
+            for (i, channel) in color.iter_mut().enumerate() {
+                *channel = u8::from_str_radix(hex.get(2 * i..2 * i + 2)?, 16).ok()?;
+            }
+            Some(SquareStyle::Color(color))
+        }
+        Some(_) => None,
+        None => Some(SquareStyle::Texture(text.to_string())),
+    }
+}
+
+fn asset_exists(path: &str) -> bool {
+    Path::new(ASSETS_DIRECTORY).join(path).exists()
+}
+
+impl Themes {
+    // Lines which cannot be parsed are reported and skipped. Without any valid
+    // entry the classic textures are used.
+    pub fn load() -> Themes {
+        let mut themes = Themes {
+            board_themes: Vec::new(),
+            piece_sets: Vec::new(),
+            board_theme: 0,
+            piece_set: 0,
+            solid_images: HashMap::new(),
+        };
+        let text = fs::read_to_string(THEMES_PATH).unwrap_or_else(|error| {
+            println!("Could not load the themes {}: {}", THEMES_PATH, error);
+            String::new()
+        });
+        for line in text.lines() {
+            let words = line.split_whitespace().collect::<Vec<&str>>();
+            match words[..] {
+                [] => {}
+                [word, ..] if word.starts_with('#') => {}
+                ["board", name, light, dark] => {
+                    match (parse_square_style(light), parse_square_style(dark)) {
+                        (Some(light), Some(dark)) => themes.board_themes.push(BoardTheme {
+                            name: name.to_string(),
+                            light,
+                            dark,
+                        }),
+                        _ => println!("Invalid board theme: {}", line),
+                    }
+                }
+                ["pieces", name, directory] => themes.piece_sets.push(PieceSet {
+                    name: name.to_string(),
+                    directory: directory.to_string(),
+                }),
+                _ => println!("Invalid theme line: {}", line),
+            }
+        }
+        if themes.board_themes.is_empty() {
+            themes.board_themes.push(BoardTheme {
+                name: String::from("classic"),
+                light: SquareStyle::Texture(TILEL_SPRITE.to_string()),
+                dark: SquareStyle::Texture(TILED_SPRITE.to_string()),
+            });
+        }
+        if themes.piece_sets.is_empty() {
+            themes.piece_sets.push(PieceSet {
+                name: String::from("classic"),
+                directory: DEFAULT_PIECE_DIRECTORY.to_string(),
+            });
+        }
+        themes
+    }
+}
+
+fn square_texture(
+    square_style: &SquareStyle,
+    fallback_color: [u8; 3],
+    solid_images: &mut HashMap<[u8; 3], Handle<Image>>,
+    asset_server: &AssetServer,
+    images: &mut Assets<Image>,
+) -> Handle<Image> {
+    let color = match square_style {
+        SquareStyle::Texture(path) if asset_exists(path) => {
+            return asset_server.load(path.as_str())
+        }
+        SquareStyle::Texture(_) => fallback_color,
+        SquareStyle::Color(color) => *color,
+    };
+    solid_images
+        .entry(color)
+        .or_insert_with(|| images.add(solid_image(color)))
+        .clone()
+}
+
+pub fn apply_board_theme(
+    game_textures: &mut GameTextures,
+    themes: &mut Themes,
+    asset_server: &AssetServer,
+    images: &mut Assets<Image>,
+) {
+    let board_theme = &themes.board_themes[themes.board_theme];
+    game_textures.tilel = square_texture(
+        &board_theme.light,
+        FALLBACK_LIGHT_COLOR,
+        &mut themes.solid_images,
+        asset_server,
+        images,
+    );
+    game_textures.tiled = square_texture(
+        &board_theme.dark,
+        FALLBACK_DARK_COLOR,
+        &mut themes.solid_images,
+        asset_server,
+        images,
+    );
+}
+
+pub fn apply_piece_set(
+    game_textures: &mut GameTextures,
+    piece_set: &PieceSet,
+    asset_server: &AssetServer,
+) {
+    for (piece_color, piece_type, file) in PIECE_FILES {
+        let mut path = format!("{}/{}", piece_set.directory, file);
+        if !asset_exists(&path) {
+            path = format!("{}/{}", DEFAULT_PIECE_DIRECTORY, file);
+        }
+        *game_textures.piece_texture_mut(piece_color, piece_type) =
+            asset_server.load(path.as_str());
+    }
+}
+
+fn menu_text(themes: &Themes) -> String {
+    let mut text = String::from("Theme (Ctrl+B to close)\nBoards:\n");
+    let mut number = 0;
+    for (i, board_theme) in themes.board_themes.iter().enumerate() {
+        number += 1;
+        let marker = if i == themes.board_theme { ">" } else { " " };
+        text += &format!("{} {} - {}\n", marker, number % 10, board_theme.name);
+    }
+    text += "Pieces:\n";
+    for (i, piece_set) in themes.piece_sets.iter().enumerate() {
+        number += 1;
+        let marker = if i == themes.piece_set { ">" } else { " " };
+        text += &format!("{} {} - {}\n", marker, number % 10, piece_set.name);
+    }
+    text
+}
+
+// The menu numbers the board themes first and the piece sets after them, only
+// the first ten entries can be chosen.
+pub fn theme_menu_system(
+    keys: Res<Input<KeyCode>>,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+    mut game_textures: ResMut<GameTextures>,
+    mut themes: ResMut<Themes>,
+    mut commands: Commands,
+    query_menu: Query<Entity, With<ThemeMenu>>,
+    query_other_menu: Query<Entity, With<DifficultyMenu>>,
+    mut query_tiles: Query<(&BoardSquare, &mut Handle<Image>), With<Tile>>,
+    query_pieces: Query<Entity, With<Position>>,
+    mut dragged_piece: ResMut<DraggedPiece>,
+    game_state: Res<GameState>,
+    animation: Res<Animation>,
+) {
+    let is_open = !query_menu.is_empty();
+    let mut close = is_open && keys.just_pressed(KeyCode::Escape);
+    if ctrl_pressed(&keys) && keys.just_pressed(KeyCode::B) {
+        if is_open {
+            close = true;
+        } else {
+            // The difficulty menu would take the same digits.
+            for entity in query_other_menu.iter() {
+                commands.entity(entity).despawn();
+            }
+            commands
+                .spawn_bundle(
+                    TextBundle::from_section(
+                        menu_text(&themes),
+                        TextStyle {
+                            font: game_textures.font.clone(),
+                            font_size: 20.,
+                            color: Color::WHITE,
+                        },
+                    )
+                    .with_style(Style {
+                        position_type: PositionType::Absolute,
+                        position: UiRect {
+                            right: Val::Px(10.),
+                            bottom: Val::Px(60.),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    }),
+                )
+                .insert(ThemeMenu);
+        }
+    }
+    if is_open && !animation.is_running() {
+        let board_theme_count = themes.board_themes.len();
+        for (i, key) in DIGIT_KEYS.iter().enumerate() {
+            if !keys.just_pressed(*key) {
+                continue;
+            }
+            if i < board_theme_count {
+                themes.board_theme = i;
+                apply_board_theme(&mut game_textures, &mut themes, &asset_server, &mut images);
+                for (board_square, mut texture) in query_tiles.iter_mut() {
+                    let Position(x, y) = board_square.position;
+                    *texture = if (x + y) % 2 == 0 {
+                        game_textures.tiled.clone()
+                    } else {
+                        game_textures.tilel.clone()
+                    };
+                }
+                close = true;
+            } else if i - board_theme_count < themes.piece_sets.len() {
+                themes.piece_set = i - board_theme_count;
+                apply_piece_set(
+                    &mut game_textures,
+                    &themes.piece_sets[themes.piece_set],
+                    &asset_server,
+                );
+                dragged_piece.entity = None;
+                respawn_pieces(
+                    &game_textures,
+                    &mut commands,
+                    &query_pieces,
+                    &game_state.board,
+                );
+                close = true;
+            }
+        }
+    }
+    if close {
+        for entity in query_menu.iter() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use crate::common::*;
+use crate::game_state::*;
+use crate::game_textures::*;
+use crate::piece::*;
+use bevy::prelude::*;
+
+const ATTACKED_SQUARE_COLOR: Color = Color::rgba(1., 0., 0., 0.2);
+const HANGING_PIECE_COLOR: Color = Color::rgba(1., 0.6, 0., 0.9);
+const CHECKING_PIECE_COLOR: Color = Color::rgba(1., 0., 0., 0.9);
+
+pub struct ShowThreats {
+    pub is_enabled: bool,
+}
+
+#[derive(Component)]
+pub struct ThreatOverlay;
+
+fn spawn_attacked_square(commands: &mut Commands, position: Position) {
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: ATTACKED_SQUARE_COLOR,
+                custom_size: Some(Vec2::new(IMAGE_SIZE.0, IMAGE_SIZE.1)),
+                ..Default::default()
+            },
+            transform: Transform::from_translation(
+                real_position(position) + Vec3::new(0., 0., 0.6),
+            ),
+            ..Default::default()
+        })
+        .insert(ThreatOverlay)
+        .insert(BoardSquare { position });
+}
+
+fn spawn_ring(commands: &mut Commands, texture: Handle<Image>, position: Position, color: Color) {
+    let mut transform =
+        Transform::from_translation(real_position(position) + Vec3::new(0., 0., 1.6));
+    transform.scale *= SCALING_FACTOR;
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color,
+                ..Default::default()
+            },
+            texture,
+            transform,
+            ..Default::default()
+        })
+        .insert(ThreatOverlay)
+        .insert(BoardSquare { position });
+}
+
+fn piece_value(piece: Piece) -> f32 {
+    piece.value(false).abs()
+}
+
+// A piece hangs when it is attacked and either undefended or attacked by a
+// cheaper piece.
+fn is_hanging(
+    game_state: &GameState,
+    piece: Piece,
+    attackers: &[Position],
+    defenders: &[Position],
+) -> bool {
+    let attacker_value = attackers
+        .iter()
+        .filter_map(|Position(x, y)| game_state.board[*x as usize][*y as usize])
+        .map(piece_value)
+        .fold(f32::INFINITY, f32::min);
+    !attackers.is_empty()
+        && piece.piece_type != PieceType::King
+        && (defenders.is_empty() || attacker_value < piece_value(piece))
+}
+
+fn spawn_threats(game_textures: &GameTextures, commands: &mut Commands, game_state: &GameState) {
+    // The threats are shown to the side of the player, or to the side to move
+    // when the engine plays both sides.
+    let piece_color = if game_state.player_moves {
+        game_state.now_moves
+    } else {
+        game_state.now_moves.opposite()
+    };
+    let attack_map = game_state.attack_map(piece_color.opposite());
+    let defence_map = game_state.attack_map(piece_color);
+    for i in 0..8 {
+        for j in 0..8 {
+            let attackers = &attack_map[i][j];
+            if attackers.is_empty() {
+                continue;
+            }
+            let position = Position(i as i8, j as i8);
+            spawn_attacked_square(commands, position);
+            if let Some(piece) = game_state.board[i][j] {
+                if piece.piece_color != piece_color {
+                    continue;
+                }
+                if piece.piece_type == PieceType::King {
+                    for attacker in attackers {
+                        spawn_ring(
+                            commands,
+                            game_textures.threat_ring.clone(),
+                            *attacker,
+                            CHECKING_PIECE_COLOR,
+                        );
+                    }
+                } else if is_hanging(game_state, piece, attackers, &defence_map[i][j]) {
+                    spawn_ring(
+                        commands,
+                        game_textures.threat_ring.clone(),
+                        position,
+                        HANGING_PIECE_COLOR,
+                    );
+                }
+            }
+        }
+    }
+}
+
+pub fn threats_system(
+    keys: Res<Input<KeyCode>>,
+    game_textures: Res<GameTextures>,
+    mut commands: Commands,
+    mut show_threats: ResMut<ShowThreats>,
+    game_state: Res<GameState>,
+    query_overlay: Query<Entity, With<ThreatOverlay>>,
+) {
+    let toggle = ctrl_pressed(&keys) && keys.just_pressed(KeyCode::T);
+    if toggle {
+        show_threats.is_enabled = !show_threats.is_enabled;
+    }
+    if !toggle && !game_state.is_changed() {
+        return;
+    }
+    for entity in query_overlay.iter() {
+        commands.entity(entity).despawn();
+    }
+    if show_threats.is_enabled {
+        spawn_threats(&game_textures, &mut commands, &game_state);
+    }
+}
@@ -0,0 +1,148 @@
+use crate::common::*;
+use crate::computer_moves::*;
+use crate::game_state::*;
+use crate::game_status::*;
+use crate::move_history::*;
+use crate::notation::*;
+use crate::piece::*;
+use crate::review::*;
+use crate::setup::*;
+use std::io::{self, BufRead, Write};
+
+const LIGHT_SQUARE: &str = "\x1b[48;5;180m";
+const DARK_SQUARE: &str = "\x1b[48;5;137m";
+const LAST_MOVE_SQUARE: &str = "\x1b[48;5;143m";
+const WHITE_PIECE: &str = "\x1b[1;97m";
+const BLACK_PIECE: &str = "\x1b[1;30m";
+const RESET: &str = "\x1b[0m";
+const HELP_MESSAGE: &str =
+    "Type moves like Nf3 or g1f3.\nundo - take back the last move\nreview - review the game and write it annotated to a PGN file\nquit - leave the game";
+
+fn piece_symbol(piece_type: PieceType) -> char {
+    match piece_type {
+        PieceType::King => '♚',
+        PieceType::Queen => '♛',
+        PieceType::Rook => '♜',
+        PieceType::Bishop => '♝',
+        PieceType::Knight => '♞',
+        PieceType::Pawn => '♟',
+    }
+}
+
+fn print_board(game_state: &GameState, last_move: Option<Move>) {
+    let flipped = unsafe { BOARD_FLIPPED };
+    let order = |i: i8| if flipped { i } else { 7 - i };
+    let mut text = String::new();
+    for row in 0..8 {
+        let y = order(row);
+        text += &format!("{} ", y + 1);
+        for column in 0..8 {
+            let x = 7 - order(column);
+            let position = Position(x, y);
+            let background = if matches!(last_move, Some((from, to)) if position == from || position == to)
+            {
+                LAST_MOVE_SQUARE
+            } else if (x + y) % 2 == 0 {
+                DARK_SQUARE
+            } else {
+                LIGHT_SQUARE
+            };
+            text += background;
+            match game_state.board[x as usize][y as usize] {
+                Some(piece) => {
+                    let color = match piece.piece_color {
+                        PieceColor::White => WHITE_PIECE,
+                        PieceColor::Black => BLACK_PIECE,
+                    };
+                    text += &format!("{} {} ", color, piece_symbol(piece.piece_type));
+                }
+                None => text += "   ",
+            }
+            text += RESET;
+        }
+        text += "\n";
+    }
+    text += "  ";
+    for column in 0..8 {
+        text += &format!(" {} ", (b'a' + (7 - order(column)) as u8) as char);
+    }
+    println!("{}", text);
+}
+
+// Plays in the terminal with the same rules, engine and program options as
+// the window.
+pub fn run() {
+    let opening_book = load_opening_book();
+    let difficulty = initial_difficulty();
+    let (mut rng, human_color) = initial_rng_and_color();
+    let mut game_state = new_game_state(human_color);
+    game_state.board = starting_board();
+    let mut move_history = MoveHistory::default();
+    let mut engine_info = EngineInfo::Idle;
+    let mut lines = io::stdin().lock().lines();
+    println!("{}", HELP_MESSAGE);
+    loop {
+        let last_move = move_history.moves.last().map(|(_, last_move)| *last_move);
+        print_board(&game_state, last_move);
+        let status = GameStatus::of(&game_state, None);
+        if status != GameStatus::Ongoing {
+            println!("{}", status.description());
+        }
+        // After the end of the game only the commands are accepted.
+        let is_game_over = status.is_game_over();
+        if !is_game_over && !game_state.player_moves {
+            let computer_move = choose_computer_move(
+                &game_state,
+                opening_book.as_ref(),
+                &difficulty,
+                &mut rng,
+                &mut engine_info,
+            );
+            let (from, to) = match computer_move {
+                Some(computer_move) => computer_move,
+                None => break,
+            };
+            print!("Engine plays {}", san(&game_state, (from, to)));
+            match engine_info.description() {
+                Some(description) => println!(", {}", description),
+                None => println!(),
+            }
+            move_history.record(&game_state, (from, to), None);
+            game_state.play_move(from, to);
+            continue;
+        }
+        match game_state.now_moves {
+            _ if is_game_over => print!("Game over: "),
+            PieceColor::White => print!("White to move: "),
+            PieceColor::Black => print!("Black to move: "),
+        }
+        io::stdout().flush().unwrap();
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => break,
+        };
+        match line.trim() {
+            "" => {}
+            "quit" => break,
+            "help" => println!("{}", HELP_MESSAGE),
+            "review" => {
+                println!("Reviewing the game ...");
+                let result = game_result(&game_state, None);
+                println!("{}", write_review(&move_history.moves, result, &mut rng));
+            }
+            "undo" => {
+                if !move_history.undo(&mut game_state, None) {
+                    println!("Nothing to undo");
+                }
+            }
+            text if is_game_over => println!("The game is over: {}", text),
+            text => match parse_move(&game_state, text) {
+                Some((from, to)) => {
+                    move_history.record(&game_state, (from, to), None);
+                    game_state.play_move(from, to);
+                }
+                None => println!("Illegal move: {}", text),
+            },
+        }
+    }
+}
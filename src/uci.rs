@@ -0,0 +1,229 @@
+use crate::common::*;
+use crate::game_state::*;
+use crate::notation::*;
+use crate::piece::*;
+use crate::program_options::*;
+use crate::search::*;
+use std::io::{self, BufRead};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const MAX_MULTI_PV: usize = 256;
+// The share of the remaining time spent on a move when playing with a clock.
+const MOVES_TO_GO: u64 = 30;
+// How often an infinite search that is finished checks for "stop".
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// Scores are centipawns from the point of view of the side to move. A won
+// line ends with the mate, so its length gives the number of moves to it. A
+// tablebase win has no mate in the line and keeps its centipawn score.
+fn uci_score(game_state: &GameState, line: &PvLine) -> String {
+    let score = match game_state.now_moves {
+        PieceColor::White => line.score,
+        PieceColor::Black => -line.score,
+    };
+    let mate_moves = (line.moves.len() as i32 + 1) / 2;
+    if score >= INFINITY * 0.75 {
+        format!("mate {}", mate_moves)
+    } else if score <= -INFINITY * 0.75 {
+        format!("mate -{}", mate_moves)
+    } else {
+        format!("cp {}", score.round() as i32)
+    }
+}
+
+fn uci_line(game_state: &GameState, line: &PvLine) -> String {
+    let mut line_state = game_state.clone();
+    let mut text = Vec::new();
+    for &(from, to) in line.moves.iter() {
+        text.push(coordinate_notation(&line_state, (from, to)));
+        line_state.move_piece(from, to, true);
+    }
+    text.join(" ")
+}
+
+// "position startpos [moves ...]" or "position fen <fen> [moves ...]". An
+// invalid position or move leaves the previous position unchanged.
+fn set_position(game_state: &mut GameState, tokens: &[&str]) {
+    let fen = tokens
+        .iter()
+        .skip(1)
+        .take_while(|token| **token != "moves")
+        .copied()
+        .collect::<Vec<&str>>()
+        .join(" ");
+    let position = match tokens.first() {
+        Some(&"startpos") => Ok(start_position()),
+        Some(&"fen") => parse_fen(&fen),
+        _ => Err(String::from("expected startpos or fen")),
+    };
+    let mut position = match position {
+        Ok(position) => position,
+        Err(error) => {
+            println!("info string invalid position: {}", error);
+            return;
+        }
+    };
+    for token in tokens.iter().skip_while(|token| **token != "moves").skip(1) {
+        match parse_move(&position, token) {
+            Some((from, to)) => position.move_piece(from, to, true),
+            None => {
+                println!("info string illegal move {}", token);
+                return;
+            }
+        }
+    }
+    *game_state = position;
+}
+
+// The limits of "go": depth, movetime, the clocks and infinite, the other
+// limits are ignored. Without any limit the search goes to the depth of the
+// program options.
+struct GoLimits {
+    depth: Option<i32>,
+    time_limit: Option<Duration>,
+    is_infinite: bool,
+}
+
+impl GoLimits {
+    fn parse(game_state: &GameState, tokens: &[&str]) -> GoLimits {
+        let value = |name: &str| {
+            let index = tokens.iter().position(|token| *token == name)?;
+            tokens.get(index + 1)?.parse::<u64>().ok()
+        };
+        let (time, increment) = match game_state.now_moves {
+            PieceColor::White => (value("wtime"), value("winc")),
+            PieceColor::Black => (value("btime"), value("binc")),
+        };
+        let time_limit = match (value("movetime"), time) {
+            (Some(movetime), _) => Some(movetime),
+            (None, Some(time)) => Some(time / MOVES_TO_GO + increment.unwrap_or(0)),
+            (None, None) => None,
+        };
+        let is_infinite = tokens.contains(&"infinite");
+        let depth = match value("depth") {
+            Some(depth) => Some(depth as i32),
+            None if time_limit.is_none() && !is_infinite => Some(unsafe { DEPTH }),
+            None => None,
+        };
+        GoLimits {
+            depth,
+            time_limit: time_limit.map(Duration::from_millis),
+            is_infinite,
+        }
+    }
+}
+
+// Prints the lines of every finished depth and the best move at the end. An
+// infinite search prints the best move only after "stop".
+fn search_position(game_state: GameState, limits: GoLimits, lines: usize, stop: Arc<AtomicBool>) {
+    let mut search = Search::new(1);
+    search.stop = Some(stop.clone());
+    search.deadline = limits
+        .time_limit
+        .map(|time_limit| Instant::now() + time_limit);
+    let mut best_move = None;
+    deepen_lines(
+        &game_state,
+        &mut search,
+        lines,
+        limits.depth,
+        |depth, pv_lines| {
+            for (index, line) in pv_lines.iter().enumerate() {
+                println!(
+                    "info depth {} multipv {} score {} pv {}",
+                    depth,
+                    index + 1,
+                    uci_score(&game_state, line),
+                    uci_line(&game_state, line)
+                );
+            }
+            best_move = pv_lines
+                .first()
+                .and_then(|line| line.moves.first())
+                .copied();
+        },
+    );
+    if limits.is_infinite {
+        while !stop.load(Ordering::Relaxed) {
+            thread::sleep(STOP_POLL_INTERVAL);
+        }
+    }
+    // A search stopped before its first depth still plays a legal move.
+    match best_move.or_else(|| game_state.generate_safe_moves().first().copied()) {
+        Some(best_move) => println!("bestmove {}", coordinate_notation(&game_state, best_move)),
+        None => println!("bestmove 0000"),
+    }
+}
+
+// The search started by "go", the commands are read while it runs.
+struct RunningSearch {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+fn stop_search(running_search: &mut Option<RunningSearch>) {
+    if let Some(running_search) = running_search.take() {
+        running_search.stop.store(true, Ordering::Relaxed);
+        running_search.thread.join().unwrap();
+    }
+}
+
+// A minimal UCI engine on standard input and output.
+pub fn run() {
+    let mut game_state = start_position();
+    let mut lines = 1;
+    let mut running_search = None;
+    for command in io::stdin().lock().lines() {
+        let command = match command {
+            Ok(command) => command,
+            Err(_) => break,
+        };
+        let tokens = command.split_whitespace().collect::<Vec<&str>>();
+        match tokens.first() {
+            Some(&"uci") => {
+                println!("id name chess");
+                println!("id author Arkadiusz Czarkowski");
+                println!(
+                    "option name MultiPV type spin default 1 min 1 max {}",
+                    MAX_MULTI_PV
+                );
+                println!("uciok");
+            }
+            Some(&"isready") => println!("readyok"),
+            Some(&"ucinewgame") => {
+                stop_search(&mut running_search);
+                game_state = start_position();
+            }
+            Some(&"setoption") => {
+                if let ["name", "MultiPV", "value", value] = tokens[1..] {
+                    match value.parse::<usize>() {
+                        Ok(value) if (1..=MAX_MULTI_PV).contains(&value) => lines = value,
+                        _ => println!("info string invalid MultiPV {}", value),
+                    }
+                }
+            }
+            Some(&"position") => {
+                stop_search(&mut running_search);
+                set_position(&mut game_state, &tokens[1..]);
+            }
+            Some(&"go") => {
+                stop_search(&mut running_search);
+                let limits = GoLimits::parse(&game_state, &tokens[1..]);
+                let search_state = game_state.clone();
+                let stop = Arc::new(AtomicBool::new(false));
+                let search_stop = stop.clone();
+                let thread = thread::spawn(move || {
+                    search_position(search_state, limits, lines, search_stop)
+                });
+                running_search = Some(RunningSearch { stop, thread });
+            }
+            Some(&"stop") => stop_search(&mut running_search),
+            Some(&"quit") => break,
+            _ => {}
+        }
+    }
+    stop_search(&mut running_search);
+}